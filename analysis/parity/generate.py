@@ -0,0 +1,65 @@
+"""Computes the expected outputs of tests/tflite_parity.rs.
+
+Runs the bundled models with the port of the int8 reference kernels of TensorFlow Lite Micro in
+reference.py, on the bundled samples and on the pseudo-random inputs generated by `noise()` in
+tests/tflite_parity.rs, and prints the dequantized outputs as the shortest float32 literals.
+
+Usage: python3 analysis/parity/generate.py
+"""
+
+import re
+from pathlib import Path
+
+from reference import Model, dequantize, f32, quantize
+
+ROOT = Path(__file__).resolve().parents[2]
+
+
+def literal(x):
+    """Returns the shortest literal parsed as the given float32, as printed by Rust."""
+    for precision in range(1, 10):
+        s = '%.*g' % (precision, x)
+        if f32(float(s)) == x:
+            return s + '.' if 'e' not in s and '.' not in s else s
+
+
+def noise(seed, n):
+    """Returns the first n elements generated by `noise()` in tests/tflite_parity.rs."""
+    out = []
+    for i in range(n):
+        v = (((i + seed * 7919) * 2654435761) & 0xFFFFFFFF) >> 24
+        out.append(v - 256 if v >= 128 else v)
+    return out
+
+
+def sample(path, name):
+    """Returns the elements of the given constant of a Rust file in samples/features."""
+    src = (ROOT / path).read_text()
+    body = src[src.index('pub const ' + name):]
+    body = body[body.index('matrix![') + 8:]
+    body = body[:body.index(']];' if name in ('PERSON', 'NO_PERSON') else '];')]
+    return [int(v) for v in re.findall(r'-?[0-9]+', body)]
+
+
+def show(model, name, q):
+    print(model, name, '[' + ', '.join(literal(v) for v in q) + ']')
+
+
+m = Model(ROOT / 'models/sine.tflite')
+xs = [f32(i * f32(0.25)) for i in range(26)]
+show('sine', '0.25 * i', [dequantize(m, m.run(quantize(m, [x])))[0] for x in xs])
+
+m = Model(ROOT / 'models/speech.tflite')
+features = 'samples/features/speech.rs'
+inputs = [(name, sample(features, name)) for name in ['YES', 'NO']]
+inputs += [('noise(%d)' % seed, noise(seed, 1960)) for seed in range(1, 5)]
+for name, x in inputs:
+    show('speech', name, dequantize(m, m.run(x)))
+
+m = Model(ROOT / 'models/person_detect.tflite')
+features = 'samples/features/person_detect.rs'
+inputs = [(name, sample(features, name)) for name in ['PERSON', 'NO_PERSON']]
+inputs += [('0.5', quantize(m, [0.5] * 9216))]
+inputs += [('noise(%d)' % seed, noise(seed, 9216)) for seed in range(1, 3)]
+for name, x in inputs:
+    show('person_detect', name, dequantize(m, m.run(x)))
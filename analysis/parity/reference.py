@@ -0,0 +1,287 @@
+"""Port of the int8 reference kernels of TensorFlow Lite Micro, in pure Python.
+
+Ported from the TensorFlow Lite reference kernels (tensorflow/lite/kernels/internal/reference/
+integer_ops/{fully_connected,conv,depthwise_conv,pooling,softmax}.h), from the quantization helpers
+of tensorflow/lite/kernels/internal/common.h and quantization_util.cc, and from the fixed-point
+arithmetic of gemmlowp (fixedpoint/fixedpoint.h).
+Only the operators of the bundled models are implemented.
+"""
+
+import math
+import struct
+
+
+# FlatBuffers reader
+
+class Table:  # table of a FlatBuffers buffer, with its fields accessed by index
+    def __init__(s, buf, pos):
+        s.buf, s.pos = buf, pos
+        vt = pos - struct.unpack_from('<i', buf, pos)[0]
+        s.vt = vt
+        s.vtlen = struct.unpack_from('<H', buf, vt)[0]
+    def off(s, field):
+        o = 4 + 2 * field
+        if o >= s.vtlen: return 0
+        return struct.unpack_from('<H', s.buf, s.vt + o)[0]
+    def scalar(s, field, fmt, default=0):
+        o = s.off(field)
+        return struct.unpack_from('<' + fmt, s.buf, s.pos + o)[0] if o else default
+    def ref(s, field):
+        o = s.off(field)
+        if not o: return None
+        p = s.pos + o
+        return p + struct.unpack_from('<I', s.buf, p)[0]
+    def table(s, field):
+        p = s.ref(field)
+        return Table(s.buf, p) if p is not None else None
+    def vector(s, field, fmt):
+        p = s.ref(field)
+        if p is None: return None
+        n = struct.unpack_from('<I', s.buf, p)[0]
+        sz = struct.calcsize('<' + fmt)
+        return [struct.unpack_from('<' + fmt, s.buf, p + 4 + i * sz)[0] for i in range(n)]
+    def tables(s, field):
+        p = s.ref(field)
+        if p is None: return []
+        n = struct.unpack_from('<I', s.buf, p)[0]
+        out = []
+        for i in range(n):
+            q = p + 4 + 4 * i
+            out.append(Table(s.buf, q + struct.unpack_from('<I', s.buf, q)[0]))
+        return out
+    def bytes_(s, field):
+        p = s.ref(field)
+        if p is None: return b''
+        n = struct.unpack_from('<I', s.buf, p)[0]
+        return s.buf[p + 4:p + 4 + n]
+
+def load(path):  # root table of a .tflite model
+    buf = open(path, 'rb').read()
+    return Table(buf, struct.unpack_from('<I', buf, 0)[0])
+
+
+# Kernels
+
+def f32(x): return struct.unpack('<f', struct.pack('<f', x))[0]
+def i32(x):
+    x &= 0xffffffff
+    return x - (1 << 32) if x & 0x80000000 else x
+def rnd(x):  # std::round, half away from zero
+    return int(math.floor(abs(x) + 0.5)) * (1 if x >= 0 else -1)
+def srdhm(a, b):  # SaturatingRoundingDoublingHighMul
+    if a == b == -(1 << 31): return (1 << 31) - 1
+    ab = a * b
+    nudge = (1 << 30) if ab >= 0 else 1 - (1 << 30)
+    v = ab + nudge
+    q = abs(v) // (1 << 31) * (1 if v >= 0 else -1)  # C++ truncating division
+    return i32(q)
+def rdbpot(x, e):  # RoundingDivideByPOT
+    mask = (1 << e) - 1
+    rem = x & mask
+    th = (mask >> 1) + (1 if x < 0 else 0)
+    return (x >> e) + (1 if rem > th else 0)
+def mbqm(x, m, s):  # MultiplyByQuantizedMultiplier
+    ls = max(s, 0); rs = max(-s, 0)
+    return rdbpot(srdhm(i32(x * (1 << ls)), m), rs)
+def quantize_multiplier(d):  # QuantizeMultiplier
+    if d == 0: return 0, 0
+    q, shift = math.frexp(d)
+    qf = rnd(q * (1 << 31))
+    if qf == (1 << 31): qf //= 2; shift += 1
+    if shift < -31: shift = 0; qf = 0
+    return qf, shift
+def sat_shl(x, e):  # SaturatingRoundingMultiplyByPOT
+    th = (1 << (31 - e)) - 1
+    if x > th: return (1 << 31) - 1
+    if x < -th: return -(1 << 31)
+    return i32(x << e)
+
+# gemmlowp fixed-point
+def exp_on_interval(a):
+    const_term = 1895147668; one_third = 715827883
+    x = i32(a + (1 << 28))
+    x2 = srdhm(x, x); x3 = srdhm(x2, x); x4 = srdhm(x2, x2)
+    x4_4 = rdbpot(x4, 2)
+    t = rdbpot(i32(srdhm(i32(x4_4 + x3), one_third) + x2), 1)
+    return i32(const_term + srdhm(const_term, i32(x + t)))
+def exp_on_negative_values(a):  # a: Q5.26, result Q0.31
+    quarter = 1 << 24
+    mask = quarter - 1
+    amq = i32((a & mask) - quarter)
+    result = exp_on_interval(sat_shl(amq, 5))
+    rem = i32(amq - a)
+    for exp, mult in [(-2, 1672461947), (-1, 1302514674), (0, 790015084), (1, 290630308),
+                      (2, 39332535), (3, 720401), (4, 242)]:
+        if rem & (1 << (26 + exp)):
+            result = srdhm(result, mult)
+    if a == 0: result = (1 << 31) - 1
+    return result
+def one_over_one_plus_x(a):
+    one0 = (1 << 31) - 1
+    s = a + one0
+    half_den = i32((s + (1 if s >= 0 else -1)) // 2 if s >= 0 else -((-s + 1) // 2))
+    x = i32(1515870810 + srdhm(half_den, -1010580540))
+    for _ in range(3):
+        hdx = srdhm(half_den, x)
+        omh = i32((1 << 29) - hdx)
+        x = i32(x + sat_shl(srdhm(x, omh), 2))
+    return sat_shl(x, 1)
+def clz(x):
+    x &= 0xffffffff
+    return 32 - x.bit_length()
+def get_reciprocal(x, int_digits):
+    h = clz(x)
+    nbou = int_digits - h
+    ssm1 = i32(((x << h) & 0xffffffff) - (1 << 31))
+    return one_over_one_plus_x(ssm1), nbou
+
+class Model:  # interpreter of a .tflite model, running its operators in order
+    def __init__(s, path):
+        m = load(path)
+        s.codes = [max(c.scalar(0, 'b'), c.scalar(3, 'i')) for c in m.tables(1)]
+        s.buffers = [b.bytes_(0) for b in m.tables(4)]
+        s.sg = m.tables(2)[0]
+        s.tensors = []
+        for t in s.sg.tables(0):
+            q = t.table(4)
+            s.tensors.append(dict(shape=t.vector(0, 'i'), type=t.scalar(1, 'b'), buffer=t.scalar(2, 'I'),
+                                  scale=q.vector(2, 'f') if q else None, zp=q.vector(3, 'q') if q else None))
+    def data(s, i):
+        t = s.tensors[i]; b = s.buffers[t['buffer']]
+        fmt = {9: 'b', 3: 'B', 2: 'i'}[t['type']]
+        return list(struct.unpack('<%d%s' % (len(b) // struct.calcsize(fmt), fmt), b))
+    def run(s, inp):
+        vals = {s.sg.vector(1, 'i')[0]: inp}
+        for op in s.sg.tables(3):
+            code = s.codes[op.scalar(0, 'I')]
+            ins = op.vector(1, 'i'); outs = op.vector(2, 'i')
+            opts = op.table(4)
+            get = lambda i: vals[i] if i in vals else s.data(i)
+            vals[outs[0]] = getattr(s, 'op_%d' % code)(ins, outs[0], opts, get)
+        return vals[s.sg.vector(2, 'i')[0]]
+    def act_range(s, act, o):
+        t = s.tensors[o]; sc = t['scale'][0]; zp = t['zp'][0]
+        qmin, qmax = (-128, 127) if t['type'] == 9 else (0, 255)
+        q = lambda f: zp + rnd(f32(f / sc))
+        if act == 0: return qmin, qmax
+        if act == 1: return max(qmin, q(0.0)), qmax
+        if act == 3: return max(qmin, q(0.0)), min(qmax, q(6.0))
+        if act == 2: return max(qmin, q(-1.0)), min(qmax, q(1.0))
+        raise Exception(act)
+    def pad(s, padding, stride, dil, inn, k):
+        eff = (k - 1) * dil + 1
+        out = (inn + stride - 1) // stride if padding == 0 else (inn + stride - eff) // stride
+        total = max((out - 1) * stride + eff - inn, 0)
+        return out, total // 2
+    def op_9(s, ins, o, opts, get):  # FULLY_CONNECTED
+        x = get(ins[0]); w = get(ins[1]); b = get(ins[2]) if len(ins) > 2 and ins[2] >= 0 else None
+        ti, tw, to = s.tensors[ins[0]], s.tensors[ins[1]], s.tensors[o]
+        units, depth = tw['shape']
+        batches = len(x) // depth
+        m, sh = quantize_multiplier(f32(ti['scale'][0] * tw['scale'][0]) / to['scale'][0])
+        act = opts.scalar(0, 'b')
+        lo, hi = s.act_range(0 if act == 4 else act, o)
+        out = []
+        for bt in range(batches):
+            for u in range(units):
+                acc = sum((w[u * depth + d] - tw['zp'][0]) * (x[bt * depth + d] - ti['zp'][0]) for d in range(depth))
+                if b: acc += b[u]
+                acc = mbqm(acc, m, sh) + to['zp'][0]
+                if act == 4:
+                    acc = rnd(f32(math.tanh(to['scale'][0] * (acc - to['zp'][0])) / to['scale'][0])) + to['zp'][0]
+                out.append(min(max(acc, lo), hi))
+        return out
+    def op_22(s, ins, o, opts, get):  # RESHAPE
+        return list(get(ins[0]))
+    def conv_common(s, ins, o, get, pad_t, sw, sh_, dw, dh, act, depthwise, dm=1):
+        x = get(ins[0]); w = get(ins[1]); b = get(ins[2]) if len(ins) > 2 and ins[2] >= 0 else None
+        ti, tw, to = s.tensors[ins[0]], s.tensors[ins[1]], s.tensors[o]
+        _, H, W, C = ti['shape']
+        if depthwise: _, KH, KW, OC = tw['shape']
+        else: OC, KH, KW, _ = tw['shape']
+        OH, ph = s.pad(pad_t, sh_, dh, H, KH)
+        OW, pw = s.pad(pad_t, sw, dw, W, KW)
+        lo, hi = s.act_range(act, o)
+        mults = [quantize_multiplier(ti['scale'][0] * tw['scale'][c if len(tw['scale']) > 1 else 0] / to['scale'][0]) for c in range(OC)]
+        zi = ti['zp'][0]
+        out = [0] * (OH * OW * OC)
+        for oy in range(OH):
+            for ox in range(OW):
+                for oc in range(OC):
+                    acc = 0
+                    for fy in range(KH):
+                        iy = oy * sh_ - ph + dh * fy
+                        if iy < 0 or iy >= H: continue
+                        for fx in range(KW):
+                            ix = ox * sw - pw + dw * fx
+                            if ix < 0 or ix >= W: continue
+                            if depthwise:
+                                ic = oc // dm
+                                acc += w[(fy * KW + fx) * OC + oc] * (x[(iy * W + ix) * C + ic] - zi)
+                            else:
+                                for ic in range(C):
+                                    acc += w[((oc * KH + fy) * KW + fx) * C + ic] * (x[(iy * W + ix) * C + ic] - zi)
+                    if b: acc += b[oc]
+                    acc = mbqm(acc, *mults[oc]) + to['zp'][0]
+                    out[(oy * OW + ox) * OC + oc] = min(max(acc, lo), hi)
+        return out
+    def op_3(s, ins, o, opts, get):  # CONV_2D
+        return s.conv_common(ins, o, get, opts.scalar(0, 'b'), opts.scalar(1, 'i'), opts.scalar(2, 'i'),
+                             opts.scalar(4, 'i', 1), opts.scalar(5, 'i', 1), opts.scalar(3, 'b'), False)
+    def op_4(s, ins, o, opts, get):  # DEPTHWISE_CONV_2D
+        return s.conv_common(ins, o, get, opts.scalar(0, 'b'), opts.scalar(1, 'i'), opts.scalar(2, 'i'),
+                             opts.scalar(5, 'i', 1), opts.scalar(6, 'i', 1), opts.scalar(4, 'b'), True,
+                             opts.scalar(3, 'i'))
+    def op_1(s, ins, o, opts, get):  # AVERAGE_POOL_2D
+        x = get(ins[0]); ti, to = s.tensors[ins[0]], s.tensors[o]
+        _, H, W, C = ti['shape']
+        pad_t, sw, sh_, fw, fh, act = [opts.scalar(i, f) for i, f in enumerate('biiiib')]
+        OH, ph = s.pad(pad_t, sh_, 1, H, fh); OW, pw = s.pad(pad_t, sw, 1, W, fw)
+        lo, hi = s.act_range(act, o)
+        out = []
+        for oy in range(OH):
+            for ox in range(OW):
+                for c in range(C):
+                    acc = 0; n = 0
+                    for fy in range(fh):
+                        for fx in range(fw):
+                            iy = oy * sh_ - ph + fy; ix = ox * sw - pw + fx
+                            if 0 <= iy < H and 0 <= ix < W:
+                                acc += x[(iy * W + ix) * C + c]; n += 1
+                    acc = (acc + n // 2) // n if acc > 0 else -((-acc + n // 2) // n)
+                    out.append(min(max(acc, lo), hi))
+        return out
+    def op_25(s, ins, o, opts, get):  # SOFTMAX
+        x = get(ins[0]); ti, to = s.tensors[ins[0]], s.tensors[o]
+        beta = opts.scalar(0, 'f')
+        depth = ti['shape'][-1]
+        real = min(beta * ti['scale'][0] * (1 << 26), (1 << 31) - 1.0)
+        mult, ls = quantize_multiplier(real)
+        assert ls >= 0
+        diff_min = -int(math.floor(1.0 * 31 * (1 << 26) / (1 << ls)))
+        out = []
+        for r in range(len(x) // depth):
+            row = x[r * depth:(r + 1) * depth]
+            mx = max(row)
+            total = 0
+            for v in row:
+                d = v - mx
+                if d >= diff_min:
+                    total = i32(total + rdbpot(exp_on_negative_values(mbqm(d, mult, ls)), 12))
+            scale, nbou = get_reciprocal(total, 12)
+            for v in row:
+                d = v - mx
+                if d >= diff_min:
+                    e = exp_on_negative_values(mbqm(d, mult, ls))
+                    y = rdbpot(srdhm(scale, e), nbou + 31 - 8) - 128
+                    out.append(min(max(y, -128), 127))
+                else:
+                    out.append(-128)
+        return out
+
+def dequantize(m, q):  # output of the model, dequantized as float32
+    t = m.tensors[m.sg.vector(2, 'i')[0]]
+    return [f32(t['scale'][0] * f32(v - t['zp'][0])) for v in q]
+def quantize(m, xs):  # input of the model, quantized from float32
+    t = m.tensors[m.sg.vector(1, 'i')[0]]
+    return [min(max(rnd(f32(f32(x) / t['scale'][0])) + t['zp'][0], -128), 127) for x in xs]
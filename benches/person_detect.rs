@@ -5,6 +5,9 @@ use microflow_macros::model;
 #[model("models/person_detect.tflite")]
 struct PersonDetect;
 
+#[model("models/person_detect.tflite", raw_weights)]
+struct PersonDetectRawWeights;
+
 fn person_detect_model(c: &mut Criterion) {
     let input = [Buffer2D::from_element([0.5])];
 
@@ -13,5 +16,17 @@ fn person_detect_model(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, person_detect_model);
+fn person_detect_model_raw_weights(c: &mut Criterion) {
+    let input = [Buffer2D::from_element([0.5])];
+
+    c.bench_function("person_detect_model_raw_weights", |b| {
+        b.iter(|| PersonDetectRawWeights::predict(input))
+    });
+}
+
+criterion_group!(
+    benches,
+    person_detect_model,
+    person_detect_model_raw_weights
+);
 criterion_main!(benches);
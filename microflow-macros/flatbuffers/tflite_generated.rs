@@ -474,13 +474,13 @@ pub mod tflite {
         since = "2.0.0",
         note = "Use associated constants instead. This will no longer be generated in 2021."
     )]
-    pub const ENUM_MAX_BUILTIN_OPERATOR: i32 = 158;
+    pub const ENUM_MAX_BUILTIN_OPERATOR: i32 = 209;
     #[deprecated(
         since = "2.0.0",
         note = "Use associated constants instead. This will no longer be generated in 2021."
     )]
     #[allow(non_camel_case_types)]
-    pub const ENUM_VALUES_BUILTIN_OPERATOR: [BuiltinOperator; 159] = [
+    pub const ENUM_VALUES_BUILTIN_OPERATOR: [BuiltinOperator; 210] = [
         BuiltinOperator::ADD,
         BuiltinOperator::AVERAGE_POOL_2D,
         BuiltinOperator::CONCATENATION,
@@ -640,6 +640,57 @@ pub mod tflite {
         BuiltinOperator::ATAN2,
         BuiltinOperator::UNSORTED_SEGMENT_MIN,
         BuiltinOperator::SIGN,
+        BuiltinOperator::BITCAST,
+        BuiltinOperator::BITWISE_XOR,
+        BuiltinOperator::RIGHT_SHIFT,
+        BuiltinOperator::STABLEHLO_LOGISTIC,
+        BuiltinOperator::STABLEHLO_ADD,
+        BuiltinOperator::STABLEHLO_DIVIDE,
+        BuiltinOperator::STABLEHLO_MULTIPLY,
+        BuiltinOperator::STABLEHLO_MAXIMUM,
+        BuiltinOperator::STABLEHLO_RESHAPE,
+        BuiltinOperator::STABLEHLO_CLAMP,
+        BuiltinOperator::STABLEHLO_CONCATENATE,
+        BuiltinOperator::STABLEHLO_BROADCAST_IN_DIM,
+        BuiltinOperator::STABLEHLO_CONVOLUTION,
+        BuiltinOperator::STABLEHLO_SLICE,
+        BuiltinOperator::STABLEHLO_CUSTOM_CALL,
+        BuiltinOperator::STABLEHLO_REDUCE,
+        BuiltinOperator::STABLEHLO_ABS,
+        BuiltinOperator::STABLEHLO_AND,
+        BuiltinOperator::STABLEHLO_COSINE,
+        BuiltinOperator::STABLEHLO_EXPONENTIAL,
+        BuiltinOperator::STABLEHLO_FLOOR,
+        BuiltinOperator::STABLEHLO_LOG,
+        BuiltinOperator::STABLEHLO_MINIMUM,
+        BuiltinOperator::STABLEHLO_NEGATE,
+        BuiltinOperator::STABLEHLO_OR,
+        BuiltinOperator::STABLEHLO_POWER,
+        BuiltinOperator::STABLEHLO_REMAINDER,
+        BuiltinOperator::STABLEHLO_RSQRT,
+        BuiltinOperator::STABLEHLO_SELECT,
+        BuiltinOperator::STABLEHLO_SUBTRACT,
+        BuiltinOperator::STABLEHLO_TANH,
+        BuiltinOperator::STABLEHLO_SCATTER,
+        BuiltinOperator::STABLEHLO_COMPARE,
+        BuiltinOperator::STABLEHLO_CONVERT,
+        BuiltinOperator::STABLEHLO_DYNAMIC_SLICE,
+        BuiltinOperator::STABLEHLO_DYNAMIC_UPDATE_SLICE,
+        BuiltinOperator::STABLEHLO_PAD,
+        BuiltinOperator::STABLEHLO_IOTA,
+        BuiltinOperator::STABLEHLO_DOT_GENERAL,
+        BuiltinOperator::STABLEHLO_REDUCE_WINDOW,
+        BuiltinOperator::STABLEHLO_SORT,
+        BuiltinOperator::STABLEHLO_WHILE,
+        BuiltinOperator::STABLEHLO_GATHER,
+        BuiltinOperator::STABLEHLO_TRANSPOSE,
+        BuiltinOperator::DILATE,
+        BuiltinOperator::STABLEHLO_RNG_BIT_GENERATOR,
+        BuiltinOperator::REDUCE_WINDOW,
+        BuiltinOperator::STABLEHLO_COMPOSITE,
+        BuiltinOperator::STABLEHLO_SHIFT_LEFT,
+        BuiltinOperator::STABLEHLO_CBRT,
+        BuiltinOperator::STABLEHLO_CASE,
     ];
 
     #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -806,9 +857,60 @@ pub mod tflite {
         pub const ATAN2: Self = Self(156);
         pub const UNSORTED_SEGMENT_MIN: Self = Self(157);
         pub const SIGN: Self = Self(158);
+        pub const BITCAST: Self = Self(159);
+        pub const BITWISE_XOR: Self = Self(160);
+        pub const RIGHT_SHIFT: Self = Self(161);
+        pub const STABLEHLO_LOGISTIC: Self = Self(162);
+        pub const STABLEHLO_ADD: Self = Self(163);
+        pub const STABLEHLO_DIVIDE: Self = Self(164);
+        pub const STABLEHLO_MULTIPLY: Self = Self(165);
+        pub const STABLEHLO_MAXIMUM: Self = Self(166);
+        pub const STABLEHLO_RESHAPE: Self = Self(167);
+        pub const STABLEHLO_CLAMP: Self = Self(168);
+        pub const STABLEHLO_CONCATENATE: Self = Self(169);
+        pub const STABLEHLO_BROADCAST_IN_DIM: Self = Self(170);
+        pub const STABLEHLO_CONVOLUTION: Self = Self(171);
+        pub const STABLEHLO_SLICE: Self = Self(172);
+        pub const STABLEHLO_CUSTOM_CALL: Self = Self(173);
+        pub const STABLEHLO_REDUCE: Self = Self(174);
+        pub const STABLEHLO_ABS: Self = Self(175);
+        pub const STABLEHLO_AND: Self = Self(176);
+        pub const STABLEHLO_COSINE: Self = Self(177);
+        pub const STABLEHLO_EXPONENTIAL: Self = Self(178);
+        pub const STABLEHLO_FLOOR: Self = Self(179);
+        pub const STABLEHLO_LOG: Self = Self(180);
+        pub const STABLEHLO_MINIMUM: Self = Self(181);
+        pub const STABLEHLO_NEGATE: Self = Self(182);
+        pub const STABLEHLO_OR: Self = Self(183);
+        pub const STABLEHLO_POWER: Self = Self(184);
+        pub const STABLEHLO_REMAINDER: Self = Self(185);
+        pub const STABLEHLO_RSQRT: Self = Self(186);
+        pub const STABLEHLO_SELECT: Self = Self(187);
+        pub const STABLEHLO_SUBTRACT: Self = Self(188);
+        pub const STABLEHLO_TANH: Self = Self(189);
+        pub const STABLEHLO_SCATTER: Self = Self(190);
+        pub const STABLEHLO_COMPARE: Self = Self(191);
+        pub const STABLEHLO_CONVERT: Self = Self(192);
+        pub const STABLEHLO_DYNAMIC_SLICE: Self = Self(193);
+        pub const STABLEHLO_DYNAMIC_UPDATE_SLICE: Self = Self(194);
+        pub const STABLEHLO_PAD: Self = Self(195);
+        pub const STABLEHLO_IOTA: Self = Self(196);
+        pub const STABLEHLO_DOT_GENERAL: Self = Self(197);
+        pub const STABLEHLO_REDUCE_WINDOW: Self = Self(198);
+        pub const STABLEHLO_SORT: Self = Self(199);
+        pub const STABLEHLO_WHILE: Self = Self(200);
+        pub const STABLEHLO_GATHER: Self = Self(201);
+        pub const STABLEHLO_TRANSPOSE: Self = Self(202);
+        pub const DILATE: Self = Self(203);
+        pub const STABLEHLO_RNG_BIT_GENERATOR: Self = Self(204);
+        pub const REDUCE_WINDOW: Self = Self(205);
+        pub const STABLEHLO_COMPOSITE: Self = Self(206);
+        pub const STABLEHLO_SHIFT_LEFT: Self = Self(207);
+        pub const STABLEHLO_CBRT: Self = Self(208);
+        pub const STABLEHLO_CASE: Self = Self(209);
 
         pub const ENUM_MIN: i32 = 0;
-        pub const ENUM_MAX: i32 = 158;
+        pub const ENUM_MAX: i32 = 209;
         pub const ENUM_VALUES: &'static [Self] = &[
             Self::ADD,
             Self::AVERAGE_POOL_2D,
@@ -969,6 +1071,57 @@ pub mod tflite {
             Self::ATAN2,
             Self::UNSORTED_SEGMENT_MIN,
             Self::SIGN,
+            Self::BITCAST,
+            Self::BITWISE_XOR,
+            Self::RIGHT_SHIFT,
+            Self::STABLEHLO_LOGISTIC,
+            Self::STABLEHLO_ADD,
+            Self::STABLEHLO_DIVIDE,
+            Self::STABLEHLO_MULTIPLY,
+            Self::STABLEHLO_MAXIMUM,
+            Self::STABLEHLO_RESHAPE,
+            Self::STABLEHLO_CLAMP,
+            Self::STABLEHLO_CONCATENATE,
+            Self::STABLEHLO_BROADCAST_IN_DIM,
+            Self::STABLEHLO_CONVOLUTION,
+            Self::STABLEHLO_SLICE,
+            Self::STABLEHLO_CUSTOM_CALL,
+            Self::STABLEHLO_REDUCE,
+            Self::STABLEHLO_ABS,
+            Self::STABLEHLO_AND,
+            Self::STABLEHLO_COSINE,
+            Self::STABLEHLO_EXPONENTIAL,
+            Self::STABLEHLO_FLOOR,
+            Self::STABLEHLO_LOG,
+            Self::STABLEHLO_MINIMUM,
+            Self::STABLEHLO_NEGATE,
+            Self::STABLEHLO_OR,
+            Self::STABLEHLO_POWER,
+            Self::STABLEHLO_REMAINDER,
+            Self::STABLEHLO_RSQRT,
+            Self::STABLEHLO_SELECT,
+            Self::STABLEHLO_SUBTRACT,
+            Self::STABLEHLO_TANH,
+            Self::STABLEHLO_SCATTER,
+            Self::STABLEHLO_COMPARE,
+            Self::STABLEHLO_CONVERT,
+            Self::STABLEHLO_DYNAMIC_SLICE,
+            Self::STABLEHLO_DYNAMIC_UPDATE_SLICE,
+            Self::STABLEHLO_PAD,
+            Self::STABLEHLO_IOTA,
+            Self::STABLEHLO_DOT_GENERAL,
+            Self::STABLEHLO_REDUCE_WINDOW,
+            Self::STABLEHLO_SORT,
+            Self::STABLEHLO_WHILE,
+            Self::STABLEHLO_GATHER,
+            Self::STABLEHLO_TRANSPOSE,
+            Self::DILATE,
+            Self::STABLEHLO_RNG_BIT_GENERATOR,
+            Self::REDUCE_WINDOW,
+            Self::STABLEHLO_COMPOSITE,
+            Self::STABLEHLO_SHIFT_LEFT,
+            Self::STABLEHLO_CBRT,
+            Self::STABLEHLO_CASE,
         ];
         /// Returns the variant's name or "" if unknown.
         pub fn variant_name(self) -> Option<&'static str> {
@@ -1132,6 +1285,57 @@ pub mod tflite {
                 Self::ATAN2 => Some("ATAN2"),
                 Self::UNSORTED_SEGMENT_MIN => Some("UNSORTED_SEGMENT_MIN"),
                 Self::SIGN => Some("SIGN"),
+                Self::BITCAST => Some("BITCAST"),
+                Self::BITWISE_XOR => Some("BITWISE_XOR"),
+                Self::RIGHT_SHIFT => Some("RIGHT_SHIFT"),
+                Self::STABLEHLO_LOGISTIC => Some("STABLEHLO_LOGISTIC"),
+                Self::STABLEHLO_ADD => Some("STABLEHLO_ADD"),
+                Self::STABLEHLO_DIVIDE => Some("STABLEHLO_DIVIDE"),
+                Self::STABLEHLO_MULTIPLY => Some("STABLEHLO_MULTIPLY"),
+                Self::STABLEHLO_MAXIMUM => Some("STABLEHLO_MAXIMUM"),
+                Self::STABLEHLO_RESHAPE => Some("STABLEHLO_RESHAPE"),
+                Self::STABLEHLO_CLAMP => Some("STABLEHLO_CLAMP"),
+                Self::STABLEHLO_CONCATENATE => Some("STABLEHLO_CONCATENATE"),
+                Self::STABLEHLO_BROADCAST_IN_DIM => Some("STABLEHLO_BROADCAST_IN_DIM"),
+                Self::STABLEHLO_CONVOLUTION => Some("STABLEHLO_CONVOLUTION"),
+                Self::STABLEHLO_SLICE => Some("STABLEHLO_SLICE"),
+                Self::STABLEHLO_CUSTOM_CALL => Some("STABLEHLO_CUSTOM_CALL"),
+                Self::STABLEHLO_REDUCE => Some("STABLEHLO_REDUCE"),
+                Self::STABLEHLO_ABS => Some("STABLEHLO_ABS"),
+                Self::STABLEHLO_AND => Some("STABLEHLO_AND"),
+                Self::STABLEHLO_COSINE => Some("STABLEHLO_COSINE"),
+                Self::STABLEHLO_EXPONENTIAL => Some("STABLEHLO_EXPONENTIAL"),
+                Self::STABLEHLO_FLOOR => Some("STABLEHLO_FLOOR"),
+                Self::STABLEHLO_LOG => Some("STABLEHLO_LOG"),
+                Self::STABLEHLO_MINIMUM => Some("STABLEHLO_MINIMUM"),
+                Self::STABLEHLO_NEGATE => Some("STABLEHLO_NEGATE"),
+                Self::STABLEHLO_OR => Some("STABLEHLO_OR"),
+                Self::STABLEHLO_POWER => Some("STABLEHLO_POWER"),
+                Self::STABLEHLO_REMAINDER => Some("STABLEHLO_REMAINDER"),
+                Self::STABLEHLO_RSQRT => Some("STABLEHLO_RSQRT"),
+                Self::STABLEHLO_SELECT => Some("STABLEHLO_SELECT"),
+                Self::STABLEHLO_SUBTRACT => Some("STABLEHLO_SUBTRACT"),
+                Self::STABLEHLO_TANH => Some("STABLEHLO_TANH"),
+                Self::STABLEHLO_SCATTER => Some("STABLEHLO_SCATTER"),
+                Self::STABLEHLO_COMPARE => Some("STABLEHLO_COMPARE"),
+                Self::STABLEHLO_CONVERT => Some("STABLEHLO_CONVERT"),
+                Self::STABLEHLO_DYNAMIC_SLICE => Some("STABLEHLO_DYNAMIC_SLICE"),
+                Self::STABLEHLO_DYNAMIC_UPDATE_SLICE => Some("STABLEHLO_DYNAMIC_UPDATE_SLICE"),
+                Self::STABLEHLO_PAD => Some("STABLEHLO_PAD"),
+                Self::STABLEHLO_IOTA => Some("STABLEHLO_IOTA"),
+                Self::STABLEHLO_DOT_GENERAL => Some("STABLEHLO_DOT_GENERAL"),
+                Self::STABLEHLO_REDUCE_WINDOW => Some("STABLEHLO_REDUCE_WINDOW"),
+                Self::STABLEHLO_SORT => Some("STABLEHLO_SORT"),
+                Self::STABLEHLO_WHILE => Some("STABLEHLO_WHILE"),
+                Self::STABLEHLO_GATHER => Some("STABLEHLO_GATHER"),
+                Self::STABLEHLO_TRANSPOSE => Some("STABLEHLO_TRANSPOSE"),
+                Self::DILATE => Some("DILATE"),
+                Self::STABLEHLO_RNG_BIT_GENERATOR => Some("STABLEHLO_RNG_BIT_GENERATOR"),
+                Self::REDUCE_WINDOW => Some("REDUCE_WINDOW"),
+                Self::STABLEHLO_COMPOSITE => Some("STABLEHLO_COMPOSITE"),
+                Self::STABLEHLO_SHIFT_LEFT => Some("STABLEHLO_SHIFT_LEFT"),
+                Self::STABLEHLO_CBRT => Some("STABLEHLO_CBRT"),
+                Self::STABLEHLO_CASE => Some("STABLEHLO_CASE"),
                 _ => None,
             }
         }
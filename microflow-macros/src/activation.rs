@@ -1,51 +1,131 @@
+use crate::quantize::{lookup_table, quantize, TokenQuantized};
 use crate::tflite_flatbuffers::tflite::ActivationFunctionType;
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::abort_call_site;
 use quote::{quote, ToTokens};
 
 /// Represents the tokenized version of the [`FusedActivation`].
-#[derive(Copy, Clone)]
-pub(crate) enum TokenFusedActivation {
+/// The activation functions depending on the output quantization hold the quantized values
+/// computed from it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TokenFusedActivation<T: TokenQuantized> {
     None,
     Relu,
-    Relu6,
+    Relu6(T),
+    ReluN1To1(T, T),
+    Tanh(Vec<T>),
+    SignBit(T),
 }
 
-impl ToTokens for TokenFusedActivation {
-    fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let ts = match self {
-            TokenFusedActivation::None => quote!(microflow::activation::FusedActivation::None),
-            TokenFusedActivation::Relu => quote!(microflow::activation::FusedActivation::Relu),
-            TokenFusedActivation::Relu6 => quote!(microflow::activation::FusedActivation::Relu6),
-        };
-        ts.to_tokens(tokens);
-    }
-}
-
-impl From<ActivationFunctionType> for TokenFusedActivation {
-    fn from(activation: ActivationFunctionType) -> Self {
+impl<T: TokenQuantized> TokenFusedActivation<T> {
+    /// Builds the [`TokenFusedActivation`] from the given activation function type, quantizing
+    /// its values with the output quantization of the operator.
+    ///
+    /// # Arguments
+    /// * `activation` - The activation function type as an [`ActivationFunctionType`]
+    /// * `scale` - The scale of the output of the operator
+    /// * `zero_point` - The zero point of the output of the operator
+    ///
+    pub(crate) fn new(activation: ActivationFunctionType, scale: f32, zero_point: T) -> Self {
         match activation {
             ActivationFunctionType::NONE => Self::None,
             ActivationFunctionType::RELU => Self::Relu,
-            ActivationFunctionType::RELU6 => Self::Relu6,
+            ActivationFunctionType::RELU6 => Self::Relu6(quantize(6., scale, zero_point)),
+            ActivationFunctionType::RELU_N1_TO_1 => Self::ReluN1To1(
+                quantize(-1., scale, zero_point),
+                quantize(1., scale, zero_point),
+            ),
+            ActivationFunctionType::TANH => Self::Tanh(lookup_table(
+                (scale, zero_point),
+                (scale, zero_point),
+                f64::tanh,
+            )),
+            ActivationFunctionType::SIGN_BIT => Self::SignBit(quantize(1., scale, zero_point)),
             unsupported => abort_call_site!(
-                "unsupported fused activation: {:?}. Supported activations are NONE, RELU, and RELU6",
+                "unsupported fused activation: {:?}. Supported activations are NONE, RELU, RELU6, RELU_N1_TO_1, TANH, and SIGN_BIT",
                 unsupported
             ),
         }
     }
 }
 
+impl<T: TokenQuantized> ToTokens for TokenFusedActivation<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let ts = match self {
+            TokenFusedActivation::None => quote!(microflow::activation::FusedActivation::None),
+            TokenFusedActivation::Relu => quote!(microflow::activation::FusedActivation::Relu),
+            TokenFusedActivation::Relu6(six) => {
+                quote!(microflow::activation::FusedActivation::Relu6(#six))
+            }
+            TokenFusedActivation::ReluN1To1(minus_one, one) => {
+                quote!(microflow::activation::FusedActivation::ReluN1To1(#minus_one, #one))
+            }
+            TokenFusedActivation::Tanh(table) => {
+                quote!(microflow::activation::FusedActivation::Tanh(&[#(#table),*]))
+            }
+            TokenFusedActivation::SignBit(one) => {
+                quote!(microflow::activation::FusedActivation::SignBit(#one))
+            }
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn fused_activation_to_tokens() {
-        let activation = TokenFusedActivation::from(ActivationFunctionType::RELU);
+        let activation = TokenFusedActivation::new(ActivationFunctionType::RELU, 0.1, 2i8);
         assert_eq!(
             activation.to_token_stream().to_string(),
             quote!(microflow::activation::FusedActivation::Relu).to_string()
         );
     }
+
+    #[test]
+    fn fused_activation_to_tokens_relu6() {
+        let activation = TokenFusedActivation::new(ActivationFunctionType::RELU6, 0.1, 2i8);
+        assert_eq!(
+            activation.to_token_stream().to_string(),
+            quote!(microflow::activation::FusedActivation::Relu6(62i8)).to_string()
+        );
+    }
+
+    #[test]
+    fn fused_activation_to_tokens_relu_n1_to_1() {
+        let activation = TokenFusedActivation::new(ActivationFunctionType::RELU_N1_TO_1, 0.1, 2i8);
+        assert_eq!(
+            activation.to_token_stream().to_string(),
+            quote!(microflow::activation::FusedActivation::ReluN1To1(
+                -8i8, 12i8
+            ))
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn fused_activation_to_tokens_tanh() {
+        let activation = TokenFusedActivation::new(ActivationFunctionType::TANH, 0.1, 2i8);
+        let TokenFusedActivation::Tanh(table) = &activation else {
+            panic!("expected the Tanh activation");
+        };
+        assert_eq!(table.len(), 256);
+        // tanh(-13) = -1, tanh(0) = 0, and tanh(0.5) = 0.46211716
+        assert_eq!((table[0], table[130], table[135]), (-8, 2, 7));
+        assert_eq!(
+            activation.to_token_stream().to_string(),
+            quote!(microflow::activation::FusedActivation::Tanh(&[#(#table),*])).to_string()
+        );
+    }
+
+    #[test]
+    fn fused_activation_to_tokens_sign_bit() {
+        let activation = TokenFusedActivation::new(ActivationFunctionType::SIGN_BIT, 0.1, 2i8);
+        assert_eq!(
+            activation.to_token_stream().to_string(),
+            quote!(microflow::activation::FusedActivation::SignBit(12i8)).to_string()
+        );
+    }
 }
@@ -0,0 +1,195 @@
+use std::mem::{align_of, size_of};
+
+use flatbuffers::{ForwardsUOffset, Vector};
+
+use crate::tflite_flatbuffers::tflite::{Buffer, Operator, Tensor, TensorType};
+
+/// The alignment of the offsets in the arena, matching the runtime `ARENA_ALIGNMENT`.
+const ARENA_ALIGNMENT: usize = 16;
+
+/// Represents the allocation of a tensor in the arena.
+#[derive(Debug)]
+pub(crate) struct TokenAllocation {
+    pub(crate) index: usize,
+    pub(crate) size: usize,
+    pub(crate) first_use: usize,
+    pub(crate) last_use: usize,
+    pub(crate) offset: usize,
+}
+
+/// Represents the memory plan of the arena holding the intermediate tensors of the model.
+#[derive(Debug)]
+pub(crate) struct TokenArena {
+    pub(crate) allocations: Vec<TokenAllocation>,
+    pub(crate) size: usize,
+}
+
+impl TokenArena {
+    /// Plans the arena of the given operators.
+    /// Every non-constant tensor is alive from the operator producing it to the last operator
+    /// consuming it, while the model inputs and outputs are alive for the whole inference.
+    ///
+    /// # Arguments
+    /// * `operators` - The model operators to run, in order
+    /// * `inputs` - The indices of the model inputs
+    /// * `outputs` - The indices of the model outputs
+    /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
+    ///
+    pub(crate) fn plan(
+        operators: &[Operator],
+        inputs: &[usize],
+        outputs: &[usize],
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        buffers: Vector<ForwardsUOffset<Buffer>>,
+    ) -> Self {
+        let is_constant = |tensor: Tensor| {
+            buffers
+                .get(tensor.buffer() as usize)
+                .data()
+                .is_some_and(|data| !data.is_empty())
+        };
+        let mut allocations: Vec<TokenAllocation> = Vec::new();
+        let mut use_tensor = |index: i32, step: usize| {
+            if index < 0 || is_constant(tensors.get(index as usize)) {
+                return;
+            }
+            let index = index as usize;
+            match allocations.iter_mut().find(|a| a.index == index) {
+                Some(allocation) => {
+                    allocation.first_use = allocation.first_use.min(step);
+                    allocation.last_use = allocation.last_use.max(step);
+                }
+                None => allocations.push(TokenAllocation {
+                    index,
+                    size: Self::tensor_size(tensors.get(index)),
+                    first_use: step,
+                    last_use: step,
+                    offset: 0,
+                }),
+            }
+        };
+        for &index in inputs {
+            use_tensor(index as i32, 0);
+        }
+        for (step, operator) in operators.iter().enumerate() {
+            for index in operator.inputs().unwrap().iter() {
+                use_tensor(index, step);
+            }
+            for index in operator.outputs().unwrap().iter() {
+                use_tensor(index, step);
+            }
+        }
+        for &index in outputs {
+            use_tensor(index as i32, operators.len());
+        }
+        Self::from_allocations(allocations)
+    }
+
+    /// Builds the [`TokenArena`] from the given allocations, assigning their offsets.
+    /// Similarly to the greedy memory planner of TensorFlow Lite Micro, the allocations are placed
+    /// from the largest to the smallest, each one at the lowest offset not conflicting with the
+    /// already placed allocations whose lifetimes overlap.
+    ///
+    /// # Arguments
+    /// * `allocations` - The allocations to place
+    ///
+    pub(crate) fn from_allocations(mut allocations: Vec<TokenAllocation>) -> Self {
+        let mut order: Vec<_> = (0..allocations.len()).collect();
+        order.sort_by_key(|&i| (usize::MAX - allocations[i].size, allocations[i].first_use));
+        let mut placed: Vec<usize> = Vec::new();
+        let mut size = 0;
+        for i in order {
+            let mut conflicts: Vec<_> = placed
+                .iter()
+                .map(|&j| &allocations[j])
+                .filter(|a| {
+                    a.first_use <= allocations[i].last_use && allocations[i].first_use <= a.last_use
+                })
+                .map(|a| (a.offset, a.offset + a.size))
+                .collect();
+            conflicts.sort();
+            let mut offset = 0;
+            for (start, end) in conflicts {
+                if offset + allocations[i].size <= start {
+                    break;
+                }
+                offset = offset.max(end.next_multiple_of(ARENA_ALIGNMENT));
+            }
+            allocations[i].offset = offset;
+            size = size.max(offset + allocations[i].size);
+            placed.push(i);
+        }
+        Self {
+            allocations,
+            size: size.next_multiple_of(ARENA_ALIGNMENT),
+        }
+    }
+
+    /// Returns the allocation of the tensor with the given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the tensor in the model
+    ///
+    pub(crate) fn allocation(&self, index: usize) -> &TokenAllocation {
+        self.allocations.iter().find(|a| a.index == index).unwrap()
+    }
+
+    /// Returns the size in bytes of the runtime tensor corresponding to the given tensor.
+    ///
+    /// # Arguments
+    /// * `tensor` - The model tensor as a [`Tensor`]
+    ///
+    fn tensor_size(tensor: Tensor) -> usize {
+        let len: usize = tensor.shape().unwrap().iter().map(|e| e as usize).product();
+        let element_size = match tensor.type_() {
+            TensorType::INT16 => size_of::<i16>(),
+            _ => size_of::<u8>(),
+        };
+        // The buffer is followed by the scale and the zero point
+        ((len + 1) * element_size + size_of::<f32>()).next_multiple_of(align_of::<f32>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocation(index: usize, size: usize, first_use: usize, last_use: usize) -> TokenAllocation {
+        TokenAllocation {
+            index,
+            size,
+            first_use,
+            last_use,
+            offset: 0,
+        }
+    }
+
+    #[test]
+    fn arena_from_allocations() {
+        let arena = TokenArena::from_allocations(vec![
+            allocation(0, 100, 0, 0),
+            allocation(1, 200, 0, 1),
+            allocation(2, 50, 1, 2),
+            allocation(3, 20, 2, 3),
+        ]);
+        assert_eq!(arena.allocation(1).offset, 0);
+        assert_eq!(arena.allocation(0).offset, 208);
+        assert_eq!(arena.allocation(2).offset, 208);
+        assert_eq!(arena.allocation(3).offset, 0);
+        assert_eq!(arena.size, 320);
+    }
+
+    #[test]
+    fn arena_from_allocations_gap() {
+        let arena = TokenArena::from_allocations(vec![
+            allocation(0, 64, 0, 0),
+            allocation(1, 64, 0, 2),
+            allocation(2, 48, 1, 2),
+        ]);
+        assert_eq!(arena.allocation(0).offset, 0);
+        assert_eq!(arena.allocation(1).offset, 64);
+        assert_eq!(arena.allocation(2).offset, 0);
+        assert_eq!(arena.size, 128);
+    }
+}
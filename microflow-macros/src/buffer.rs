@@ -1,8 +1,22 @@
+use std::mem::size_of;
 use std::ops::Deref;
 
 use nalgebra::DMatrix;
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
 use quote::{quote, ToTokens};
+use simba::scalar::SubsetOf;
+
+use crate::quantize::TokenQuantized;
+
+/// Represents the emission mode of the constant buffers in the generated code.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum TokenBufferEmission {
+    /// The buffers are emitted as `nalgebra` matrix literals.
+    Matrix,
+    /// The buffers are emitted as byte strings, reinterpreted as the runtime buffers at compile
+    /// time.
+    Raw,
+}
 
 /// Represents the tokenized version of the `Buffer2D`.
 #[derive(Debug)]
@@ -19,6 +33,14 @@ impl<T> TokenBuffer2D<T> {
     }
 }
 
+impl<T: TokenQuantized> TokenBuffer2D<T> {
+    /// Returns the tokens of the buffer emitted as a byte string, in the column-major layout of
+    /// the `Buffer2D`.
+    pub(crate) fn raw_tokens(&self) -> TokenStream2 {
+        raw_tokens(self.iter())
+    }
+}
+
 impl<T> From<DMatrix<T>> for TokenBuffer2D<T> {
     fn from(matrix: DMatrix<T>) -> Self {
         Self(Some(matrix))
@@ -52,6 +74,14 @@ impl<T> TokenBuffer4D<T> {
     }
 }
 
+impl<T: TokenQuantized> TokenBuffer4D<T> {
+    /// Returns the tokens of the buffer emitted as a byte string, in the layout of the
+    /// `Buffer4D`, i.e., batches of column-major matrices of channels.
+    pub(crate) fn raw_tokens(&self) -> TokenStream2 {
+        raw_tokens(self.iter().flat_map(|batch| batch.iter().flatten()))
+    }
+}
+
 impl<T> From<Vec<DMatrix<Vec<T>>>> for TokenBuffer4D<T> {
     fn from(data: Vec<DMatrix<Vec<T>>>) -> Self {
         Self(Some(data))
@@ -86,6 +116,24 @@ impl<T> Deref for TokenBuffer4D<T> {
     }
 }
 
+/// Returns the tokens reinterpreting the given 8-bit values as a runtime buffer.
+///
+/// # Arguments
+/// * `values` - The values of the buffer, in the memory layout of the runtime buffer
+///
+fn raw_tokens<'a, T: TokenQuantized>(values: impl Iterator<Item = &'a T>) -> TokenStream2 {
+    assert_eq!(
+        size_of::<T>(),
+        1,
+        "only 8-bit buffers can be emitted as bytes"
+    );
+    let bytes: Vec<_> = values
+        .map(|e| SubsetOf::<i64>::to_superset(e) as u8)
+        .collect();
+    let bytes = Literal::byte_string(&bytes);
+    quote!(unsafe { microflow::buffer::from_raw_bytes(*#bytes) })
+}
+
 #[cfg(test)]
 mod tests {
     use nalgebra::dmatrix;
@@ -138,6 +186,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn buffer_2d_raw_tokens() {
+        let buffer = TokenBuffer2D::from(dmatrix![
+            1i8, 2, 3;
+            4,   5, -6
+        ]);
+        let bytes = Literal::byte_string(&[1, 4, 2, 5, 3, 250]);
+        assert_eq!(
+            buffer.raw_tokens().to_string(),
+            quote!(unsafe { microflow::buffer::from_raw_bytes(*#bytes) }).to_string()
+        );
+    }
+
     #[test]
     fn buffer_4d_new() {
         assert_eq!(TokenBuffer4D::<i8>::new().0, None);
@@ -169,4 +230,17 @@ mod tests {
             .to_string()
         );
     }
+
+    #[test]
+    fn buffer_4d_raw_tokens() {
+        let buffer = setup_4d();
+        let bytes = Literal::byte_string(&[
+            7, 8, 13, 14, 9, 10, 15, 16, 11, 12, 17, 18, 19, 20, 25, 26, 21, 22, 27, 28, 23, 24,
+            29, 30,
+        ]);
+        assert_eq!(
+            buffer.raw_tokens().to_string(),
+            quote!(unsafe { microflow::buffer::from_raw_bytes(*#bytes) }).to_string()
+        );
+    }
 }
@@ -0,0 +1,245 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote, ToTokens};
+use syn::Ident;
+
+use crate::tflite_flatbuffers::tflite::{Tensor, TensorType};
+
+/// The strict and reserved keywords of Rust (in lowercase), which cannot name struct fields.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Represents an input or an output of the model, i.e., a quantized tensor exchanged by the
+/// `predict` functions.
+pub(crate) struct TokenModelTensor {
+    pub(crate) index: usize,
+    pub(crate) name: Ident,
+    pub(crate) shape: Vec<usize>,
+    pub(crate) type_: TokenStream2,
+    pub(crate) scale: Vec<TokenStream2>,
+    pub(crate) zero_point: Vec<TokenStream2>,
+}
+
+impl TokenModelTensor {
+    /// Builds the [`TokenModelTensor`] from the given model tensor.
+    ///
+    /// # Arguments
+    /// * `tensor` - The model tensor as a [`Tensor`]
+    /// * `index` - The index of the tensor in the model
+    /// * `name` - The name of the tensor, as a valid identifier
+    /// * `kind` - The kind of the tensor, either `input` or `output`
+    ///
+    pub(crate) fn new(tensor: Tensor, index: usize, name: Ident, kind: &str) -> Self {
+        let mut shape: Vec<_> = tensor.shape().unwrap().iter().map(|e| e as usize).collect();
+        if shape.len() == 1 {
+            shape.insert(0, 1);
+        }
+        if shape.len() != 2 && shape.len() != 4 {
+            abort_call_site!(
+                "unsupported {} tensor rank: {} (shape {:?}). Supported ranks are 2 and 4",
+                kind,
+                shape.len(),
+                shape
+            );
+        }
+        let quantization = tensor.quantization().unwrap();
+        let zero_point = quantization.zero_point().unwrap().iter();
+        let (type_, zero_point) = match tensor.type_() {
+            TensorType::INT8 => (
+                quote!(i8),
+                zero_point.map(|e| (e as i8).to_token_stream()).collect(),
+            ),
+            TensorType::UINT8 => (
+                quote!(u8),
+                zero_point.map(|e| (e as u8).to_token_stream()).collect(),
+            ),
+            TensorType::INT16 => (
+                quote!(i16),
+                zero_point.map(|e| (e as i16).to_token_stream()).collect(),
+            ),
+            type_ => abort_call_site!(
+                "unsupported {} tensor type: {:?}. Supported {} types are INT8, UINT8, and INT16",
+                kind,
+                type_,
+                kind
+            ),
+        };
+        Self {
+            index,
+            name,
+            shape,
+            type_,
+            scale: quantization
+                .scale()
+                .unwrap()
+                .iter()
+                .map(|e| e.to_token_stream())
+                .collect(),
+            zero_point,
+        }
+    }
+
+    /// Returns the tokens of the buffer type holding the tensor elements as `f32`.
+    pub(crate) fn buffer_tokens(&self) -> TokenStream2 {
+        self.buffer_tokens_of(quote!(f32))
+    }
+
+    /// Returns the tokens of the buffer type holding the quantized tensor elements.
+    pub(crate) fn quantized_buffer_tokens(&self) -> TokenStream2 {
+        self.buffer_tokens_of(self.type_.clone())
+    }
+
+    /// Returns the tokens of the buffer type holding the tensor elements as the given type.
+    ///
+    /// # Arguments
+    /// * `ty` - The tokens of the element type
+    ///
+    fn buffer_tokens_of(&self, ty: TokenStream2) -> TokenStream2 {
+        let shape = &self.shape;
+        match shape.len() {
+            2 => quote!(microflow::buffer::Buffer2D<#ty, #(#shape),*>),
+            _ => quote!(microflow::buffer::Buffer4D<#ty, #(#shape),*>),
+        }
+    }
+
+    /// Returns the tokens of the tensor kind, i.e., either `Tensor2D` or `Tensor4D`.
+    pub(crate) fn kind_tokens(&self) -> TokenStream2 {
+        match self.shape.len() {
+            2 => quote!(microflow::tensor::Tensor2D),
+            _ => quote!(microflow::tensor::Tensor4D),
+        }
+    }
+
+    /// Returns the tokens of the tensor type.
+    pub(crate) fn type_tokens(&self) -> TokenStream2 {
+        let kind = self.kind_tokens();
+        let type_ = &self.type_;
+        let shape = &self.shape;
+        quote!(#kind<#type_, #(#shape),*, 1usize>)
+    }
+}
+
+/// Returns the identifiers of the given tensor names, converted to snake case.
+/// Empty and duplicate names are replaced by the given prefix followed by the position of the
+/// tensor, while the names starting with a digit and the Rust keywords are prefixed.
+///
+/// # Arguments
+/// * `names` - The names of the tensors
+/// * `prefix` - The prefix of the replacement names
+///
+pub(crate) fn tensor_names(names: &[&str], prefix: &str) -> Vec<Ident> {
+    let names: Vec<_> = names.iter().map(|name| snake_case(name)).collect();
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if name.is_empty() || names.iter().filter(|n| *n == name).count() > 1 {
+                format_ident!("{}_{}", prefix, i)
+            } else if name.starts_with(|c: char| c.is_ascii_digit())
+                || KEYWORDS.contains(&name.as_str())
+            {
+                format_ident!("{}_{}", prefix, name)
+            } else {
+                format_ident!("{}", name)
+            }
+        })
+        .collect()
+}
+
+/// Converts the given tensor name to snake case, replacing the invalid characters.
+///
+/// # Arguments
+/// * `name` - The tensor name
+///
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase()
+                && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+            {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else if !snake.is_empty() && !snake.ends_with('_') {
+            snake.push('_');
+        }
+        previous = Some(c);
+    }
+    snake.trim_end_matches('_').to_string()
+}
+
+/// Returns the given tokens as a tuple, or as they are if there is only one of them.
+///
+/// # Arguments
+/// * `tokens` - The tokens of the tuple elements
+///
+pub(crate) fn tuple_tokens(tokens: &[TokenStream2]) -> TokenStream2 {
+    match tokens {
+        [token] => token.clone(),
+        tokens => quote!((#(#tokens),*)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tensor_names_snake_case() {
+        assert_eq!(
+            tensor_names(
+                &["StatefulPartitionedCall:0", "serving_default_image:0", "3d"],
+                "output"
+            ),
+            vec![
+                format_ident!("stateful_partitioned_call_0"),
+                format_ident!("serving_default_image_0"),
+                format_ident!("output_3d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tensor_names_keyword() {
+        assert_eq!(
+            tensor_names(&["type", "Loop", "self:0", "self"], "output"),
+            vec![
+                format_ident!("output_type"),
+                format_ident!("output_loop"),
+                format_ident!("self_0"),
+                format_ident!("output_self"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tensor_names_duplicate() {
+        assert_eq!(
+            tensor_names(&["scores", "Scores", ""], "output"),
+            vec![
+                format_ident!("output_0"),
+                format_ident!("output_1"),
+                format_ident!("output_2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tuple_tokens_single() {
+        assert_eq!(
+            tuple_tokens(&[quote!(a)]).to_string(),
+            quote!(a).to_string()
+        );
+        assert_eq!(
+            tuple_tokens(&[quote!(a), quote!(b)]).to_string(),
+            quote!((a, b)).to_string()
+        );
+    }
+}
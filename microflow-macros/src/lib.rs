@@ -7,33 +7,46 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use proc_macro_error::{abort_call_site, proc_macro_error};
-use std::fs;
+use proc_macro_error::{abort, abort_call_site, proc_macro_error};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
 
 use proc_macro2::TokenStream as TokenStream2;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, ItemStruct};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_macro_input, Index, ItemStruct};
 
+use crate::arena::{TokenAllocation, TokenArena};
+use crate::buffer::TokenBufferEmission;
+use crate::io::TokenModelTensor;
+use crate::quantize::TokenRequantization;
+use crate::tensor::tensor_ident;
 use crate::tflite_flatbuffers::tflite::TensorType;
 use ops::*;
+use structmeta::Flag;
 use structmeta::StructMeta;
 use syn::LitStr;
-use tflite_flatbuffers::tflite::{root_as_model, BuiltinOperator};
+use tflite_flatbuffers::tflite::{BuiltinOperator, Operator};
 
 mod activation;
+mod arena;
 mod buffer;
+mod io;
 mod ops;
 mod quantize;
 mod tensor;
 #[path = "../flatbuffers/tflite_generated.rs"]
 #[allow(unused_imports)]
+#[allow(unknown_lints, mismatched_lifetime_syntaxes)]
 #[allow(clippy::all)]
 mod tflite_flatbuffers;
+mod validation;
 
 #[derive(StructMeta)]
 struct Args {
     #[struct_meta(unnamed)]
     path: LitStr,
+    integer_only: Flag,
+    raw_weights: Flag,
 }
 
 /// The entry point of MicroFlow.
@@ -41,20 +54,45 @@ struct Args {
 /// function based on the given model.
 /// The macro takes as input the path of the model, which must be in the TensorFlow Lite format
 /// (`.tflite`).
+/// Relative paths are resolved from the directory containing the `Cargo.toml` of the crate, and
+/// the crate is rebuilt whenever the model changes.
+/// The optional `integer_only` argument (or the `integer-only` feature) makes the operators
+/// requantize with integer-only arithmetic, e.g., `#[model("path/to/model.tflite", integer_only)]`.
+/// The optional `raw_weights` argument (or the `raw-weights` feature) emits the weights as byte
+/// strings instead of matrix literals, drastically reducing the compilation time and memory of
+/// large models, e.g., `#[model("path/to/model.tflite", raw_weights)]`.
+/// Models with multiple inputs take them as a tuple, while models with multiple outputs return
+/// them as a generated `<Model>Output` struct, with the fields named after the output tensors.
+/// The `predict_into()` variants borrow the input and write the output into the given buffer, so
+/// that large tensors are never moved.
+/// The operators write their outputs into the arena, which the `_with_arena()` variants take from
+/// the caller, while the other variants place the whole arena of `ARENA_SIZE` bytes on the stack:
+/// stack-constrained targets must pass a `static` arena to the `_with_arena()` variants, the only
+/// way to keep the stack usage independent of the size of the tensors.
 #[proc_macro_error]
 #[proc_macro_attribute]
 pub fn model(args: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as Args);
     let item = parse_macro_input!(item as ItemStruct);
 
-    let buf = fs::read(args.path.value()).unwrap_or_else(|_| {
-        abort_call_site!(
+    let path = model_path(&args.path.value());
+    let buf = fs::read(&path).unwrap_or_else(|_| {
+        abort!(
+            args.path,
             "couldn't find '{}', please provide a valid path",
-            &args.path.value()
+            path.display();
+            note = "relative paths are resolved from the directory of the crate manifest"
         )
     });
-    let model = root_as_model(&buf).unwrap_or_else(|_| {
-        abort_call_site!("invalid model, please provide a valid TensorFlow Lite model")
+    // The model is validated as a whole, so that it can be parsed without further checks
+    let model = validation::load(&buf).unwrap_or_else(|e| {
+        abort!(
+            args.path,
+            "invalid model '{}': {}",
+            path.display(),
+            e;
+            help = "please provide a valid and fully quantized TensorFlow Lite model"
+        )
     });
 
     let ident = &item.ident;
@@ -63,141 +101,366 @@ pub fn model(args: TokenStream, item: TokenStream) -> TokenStream {
     let tensors = subgraph.tensors().unwrap();
     let buffers = model.buffers().unwrap();
 
-    let input = tensors.get(subgraph.inputs().unwrap().get(0) as usize);
-    let mut input_shape: Vec<_> = input.shape().unwrap().iter().map(|e| e as usize).collect();
-    if input_shape.len() == 1 {
-        input_shape.insert(0, 1);
-    }
-    let input_type = match input.type_() {
-        TensorType::INT8 => quote!(i8),
-        TensorType::UINT8 => quote!(u8),
-        input_type => abort_call_site!(
-            "unsupported input tensor type: {:?}. Supported input types are INT8 and UINT8",
-            input_type
-        ),
-    };
-    let input_tensor = match input_shape.len() {
-        2 => quote!(Tensor2D),
-        4 => quote!(Tensor4D),
-        rank => abort_call_site!(
-            "unsupported input tensor rank: {} (shape {:?}). Supported ranks are 2 and 4",
-            rank,
-            input_shape
-        ),
-    };
-    let input_buffer = match input_shape.len() {
-        2 => quote!(Buffer2D),
-        4 => quote!(Buffer4D),
-        rank => abort_call_site!(
-            "unsupported input tensor rank for buffer mapping: {} (shape {:?}). Supported ranks are 2 and 4",
-            rank,
-            input_shape
-        ),
-    };
-    let input_scale: Vec<_> = input
-        .quantization()
-        .unwrap()
-        .scale()
+    let operator_codes = model.operator_codes().unwrap();
+    let builtin_code = |operator: Operator| validation::builtin_code(operator_codes, operator);
+
+    // The float inputs and outputs are quantized and dequantized by the `predict` functions, hence
+    // the QUANTIZE and DEQUANTIZE operators at the boundaries of the model are skipped
+    let mut boundary_operators = Vec::new();
+    let mut input_indices: Vec<_> = subgraph
+        .inputs()
         .unwrap()
         .iter()
-        .map(|e| e.to_token_stream())
+        .map(|e| e as usize)
         .collect();
-    let input_zero_point: Vec<_> = match input.type_() {
-        TensorType::INT8 => input
-            .quantization()
+    for input_index in input_indices.iter_mut() {
+        if tensors.get(*input_index).type_() != TensorType::FLOAT32 {
+            continue;
+        }
+        let (index, operator) = subgraph
+            .operators()
             .unwrap()
-            .zero_point()
+            .iter()
+            .enumerate()
+            .find(|&(_, operator)| {
+                builtin_code(operator) == BuiltinOperator::QUANTIZE
+                    && operator.inputs().unwrap().get(0) as usize == *input_index
+            })
+            .unwrap_or_else(|| {
+                abort_call_site!(
+                    "unsupported input tensor type: FLOAT32. Float inputs must be quantized by a QUANTIZE operator"
+                )
+            });
+        boundary_operators.push(index);
+        *input_index = operator.outputs().unwrap().get(0) as usize;
+    }
+    let mut output_indices: Vec<_> = subgraph
+        .outputs()
+        .unwrap()
+        .iter()
+        .map(|e| e as usize)
+        .collect();
+    for output_index in output_indices.iter_mut() {
+        if tensors.get(*output_index).type_() != TensorType::FLOAT32 {
+            continue;
+        }
+        let (index, operator) = subgraph
+            .operators()
             .unwrap()
             .iter()
-            .map(|e| (e as i8).to_token_stream())
-            .collect(),
-        TensorType::UINT8 => input
-            .quantization()
+            .enumerate()
+            .find(|&(_, operator)| {
+                builtin_code(operator) == BuiltinOperator::DEQUANTIZE
+                    && operator.outputs().unwrap().get(0) as usize == *output_index
+            })
+            .unwrap_or_else(|| {
+                abort_call_site!(
+                    "unsupported output tensor type: FLOAT32. Float outputs must be dequantized by a DEQUANTIZE operator"
+                )
+            });
+        boundary_operators.push(index);
+        *output_index = operator.inputs().unwrap().get(0) as usize;
+    }
+    let operators: Vec<_> = subgraph
+        .operators()
+        .unwrap()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !boundary_operators.contains(index))
+        .collect();
+
+    // The inputs and outputs are named after the original tensors of the model
+    let input_names = io::tensor_names(
+        &subgraph
+            .inputs()
             .unwrap()
-            .zero_point()
+            .iter()
+            .map(|e| tensors.get(e as usize).name().unwrap_or_default())
+            .collect::<Vec<_>>(),
+        "input",
+    );
+    let inputs: Vec<_> = input_indices
+        .iter()
+        .zip(input_names)
+        .map(|(&index, name)| TokenModelTensor::new(tensors.get(index), index, name, "input"))
+        .collect();
+    let output_names = io::tensor_names(
+        &subgraph
+            .outputs()
             .unwrap()
             .iter()
-            .map(|e| (e as u8).to_token_stream())
-            .collect(),
-        input_type => abort_call_site!(
-            "unsupported input zero-point tensor type: {:?}. Supported types are INT8 and UINT8",
-            input_type
-        ),
+            .map(|e| tensors.get(e as usize).name().unwrap_or_default())
+            .collect::<Vec<_>>(),
+        "output",
+    );
+    let outputs: Vec<_> = output_indices
+        .iter()
+        .zip(output_names)
+        .map(|(&index, name)| TokenModelTensor::new(tensors.get(index), index, name, "output"))
+        .collect();
+
+    let requantization = if args.integer_only.value() || cfg!(feature = "integer-only") {
+        TokenRequantization::Integer
+    } else {
+        TokenRequantization::Float
     };
 
-    let operators = subgraph.operators().unwrap();
+    let emission = if args.raw_weights.value() || cfg!(feature = "raw-weights") {
+        TokenBufferEmission::Raw
+    } else {
+        TokenBufferEmission::Matrix
+    };
+
+    let arena = TokenArena::plan(
+        &operators
+            .iter()
+            .map(|&(_, operator)| operator)
+            .collect::<Vec<_>>(),
+        &input_indices,
+        &output_indices,
+        tensors,
+        buffers,
+    );
+    let arena_size = arena.size;
+
     let mut layers = TokenStream2::new();
-    for (index, operator) in operators.iter().enumerate() {
-        let layer: Box<dyn ToTokens> = match BuiltinOperator(
-            model
-                .operator_codes()
-                .unwrap()
-                .get(operator.opcode_index() as usize)
-                .deprecated_builtin_code() as i32,
-        ) {
+    for &(index, operator) in &operators {
+        let layer: Box<dyn ToTokens> = match builtin_code(operator) {
+            BuiltinOperator::ADD => add::parse(operator, tensors, buffers, requantization),
             BuiltinOperator::FULLY_CONNECTED => {
-                fully_connected::parse(operator, tensors, buffers, index)
+                fully_connected::parse(operator, tensors, buffers, index, requantization, emission)
+            }
+            BuiltinOperator::DEPTHWISE_CONV_2D => depthwise_conv_2d::parse(
+                operator,
+                tensors,
+                buffers,
+                index,
+                requantization,
+                emission,
+            ),
+            BuiltinOperator::CONV_2D => {
+                conv_2d::parse(operator, tensors, buffers, index, requantization, emission)
             }
-            BuiltinOperator::DEPTHWISE_CONV_2D => {
-                depthwise_conv_2d::parse(operator, tensors, buffers, index)
+            BuiltinOperator::AVERAGE_POOL_2D => {
+                average_pool_2d::parse(operator, tensors, requantization)
             }
-            BuiltinOperator::CONV_2D => conv_2d::parse(operator, tensors, buffers, index),
-            BuiltinOperator::AVERAGE_POOL_2D => average_pool_2d::parse(operator, tensors),
-            BuiltinOperator::SOFTMAX => softmax::parse(operator, tensors),
-            BuiltinOperator::RESHAPE => Box::new(reshape::parse(operator, tensors)),
-            unsupported_op => abort_call_site!("unsupported operator: {:?}", unsupported_op),
+            BuiltinOperator::MAX_POOL_2D => max_pool_2d::parse(operator, tensors),
+            BuiltinOperator::SOFTMAX => softmax::parse(operator, tensors, index, requantization),
+            BuiltinOperator::RESHAPE => reshape::parse(operator, tensors),
+            code @ (BuiltinOperator::LOGISTIC
+            | BuiltinOperator::TANH
+            | BuiltinOperator::EXP
+            | BuiltinOperator::LOG
+            | BuiltinOperator::SQRT
+            | BuiltinOperator::RSQRT
+            | BuiltinOperator::ABS
+            | BuiltinOperator::NEG
+            | BuiltinOperator::FLOOR
+            | BuiltinOperator::ELU
+            | BuiltinOperator::GELU
+            | BuiltinOperator::HARD_SWISH) => lut_unary::parse(operator, tensors, index, code),
+            BuiltinOperator::QUANTIZE => ops::quantize::parse(operator, tensors, requantization),
+            unsupported_op => {
+                abort_call_site!("unsupported operator {}: {:?}", index, unsupported_op)
+            }
+        };
+        let output_index = operator.outputs().unwrap().get(0) as usize;
+        let output_ident = tensor_ident(output_index);
+        let TokenAllocation { offset, size, .. } = arena.allocation(output_index);
+        // The layer writes its output into the slot reserved in the arena, and it is scoped, so
+        // that the output is then borrowed immutably by the following layers
+        let ts = quote! {
+            let #output_ident: &_ = {
+                let #output_ident = unsafe { arena.slot(#offset, #size) };
+                #layer
+                #output_ident
+            };
         };
-        layer.to_tokens(&mut layers)
+        ts.to_tokens(&mut layers)
     }
 
-    let output = tensors.get(subgraph.outputs().unwrap().get(0) as usize);
-    let mut output_shape: Vec<_> = output.shape().unwrap().iter().map(|e| e as usize).collect();
-    if output_shape.len() == 1 {
-        output_shape.insert(0, 1);
-    }
-    let output_type = match output.type_() {
-        TensorType::INT8 => quote!(i8),
-        TensorType::UINT8 => quote!(u8),
-        output_type => abort_call_site!(
-            "unsupported output tensor type: {:?}. Supported output types are INT8 and UINT8",
-            output_type
-        ),
+    // Multiple inputs are passed as a tuple, while multiple outputs are returned as a struct
+    // The references to the input values, from the input of the `predict` functions, either taken
+    // by value or borrowed
+    let input_references = |borrowed: bool| -> Vec<_> {
+        match inputs.len() {
+            1 => match borrowed {
+                true => vec![quote!(input)],
+                false => vec![quote!(&input)],
+            },
+            len => (0..len)
+                .map(|i| {
+                    let i = Index::from(i);
+                    quote!(&input.#i)
+                })
+                .collect(),
+        }
     };
-    let output_tensor = match output_shape.len() {
-        2 => quote!(Tensor2D),
-        4 => quote!(Tensor4D),
-        rank => abort_call_site!(
-            "unsupported output tensor rank: {} (shape {:?}). Supported ranks are 2 and 4",
-            rank,
-            output_shape
-        ),
+    let input_buffer =
+        io::tuple_tokens(&inputs.iter().map(|i| i.buffer_tokens()).collect::<Vec<_>>());
+    let input_quantized_buffer = io::tuple_tokens(
+        &inputs
+            .iter()
+            .map(|i| i.quantized_buffer_tokens())
+            .collect::<Vec<_>>(),
+    );
+    // The inputs are quantized or copied directly into their slots of the arena
+    let input_writes = |borrowed: bool, method: TokenStream2| -> Vec<_> {
+        inputs
+            .iter()
+            .zip(input_references(borrowed))
+            .map(|(input, reference)| {
+                let ty = input.type_tokens();
+                let scale = &input.scale;
+                let zero_point = &input.zero_point;
+                let TokenAllocation { offset, size, .. } = arena.allocation(input.index);
+                quote! {
+                    unsafe { arena.slot::<#ty>(#offset, #size) }
+                        .#method(#reference, [#(#scale),*], [#(#zero_point),*]);
+                }
+            })
+            .collect()
     };
-    let output_buffer = match output_shape.len() {
-        2 => quote!(Buffer2D),
-        4 => quote!(Buffer4D),
-        rank => abort_call_site!(
-            "unsupported output tensor rank for buffer mapping: {} (shape {:?}). Supported ranks are 2 and 4",
-            rank,
-            output_shape
+    let (input_quantize, input_quantize_borrowed) = (
+        input_writes(false, quote!(quantize_from)),
+        input_writes(true, quote!(quantize_from)),
+    );
+    let (input_copy, input_copy_borrowed) = (
+        input_writes(false, quote!(copy_from)),
+        input_writes(true, quote!(copy_from)),
+    );
+    let input_slots: Vec<_> = inputs
+        .iter()
+        .map(|input| {
+            let ident = tensor_ident(input.index);
+            let ty = input.type_tokens();
+            let TokenAllocation { offset, size, .. } = arena.allocation(input.index);
+            quote!(let #ident: &#ty = unsafe { arena.slot(#offset, #size) };)
+        })
+        .collect();
+
+    let output_tensor = io::tuple_tokens(
+        &outputs
+            .iter()
+            .map(|o| {
+                let ty = o.type_tokens();
+                quote!(&#ty)
+            })
+            .collect::<Vec<_>>(),
+    );
+    let output_idents = io::tuple_tokens(
+        &outputs
+            .iter()
+            .map(|o| tensor_ident(o.index).to_token_stream())
+            .collect::<Vec<_>>(),
+    );
+    let (output_struct, output_buffer, output_dequantize, output_dequantize_into) = match &outputs[..]
+    {
+        [output] => (
+            quote!(),
+            output.buffer_tokens(),
+            quote!(Self::predict_inner(arena).dequantize()),
+            quote!(Self::predict_inner(arena).dequantize_into(output);),
         ),
+        outputs => {
+            let vis = &item.vis;
+            let output_struct_ident = format_ident!("{}Output", ident);
+            let names: Vec<_> = outputs.iter().map(|o| &o.name).collect();
+            let buffers: Vec<_> = outputs.iter().map(|o| o.buffer_tokens()).collect();
+            let indices: Vec<_> = (0..outputs.len()).map(Index::from).collect();
+            let doc = format!("The outputs of [`{}`].", ident);
+            (
+                quote! {
+                    #[doc = #doc]
+                    #[derive(Debug, PartialEq)]
+                    #vis struct #output_struct_ident {
+                        #(pub #names: #buffers),*
+                    }
+                },
+                output_struct_ident.to_token_stream(),
+                quote! {
+                    let output = Self::predict_inner(arena);
+                    #output_struct_ident {
+                        #(#names: output.#indices.dequantize()),*
+                    }
+                },
+                quote! {
+                    let tensors = Self::predict_inner(arena);
+                    #(tensors.#indices.dequantize_into(&mut output.#names);)*
+                },
+            )
+        }
     };
 
+    // The model is included (and discarded), so that Cargo rebuilds the crate when it changes
+    let path = path.to_str().unwrap_or_else(|| {
+        abort!(
+            args.path,
+            "invalid path '{}', please provide a valid UTF-8 path",
+            path.display()
+        )
+    });
     let ts = quote! {
         #item
+        const _: &[u8] = include_bytes!(#path);
+        #output_struct
         impl #ident {
-            pub fn predict(input: microflow::buffer::#input_buffer<f32, #(#input_shape),*>) -> microflow::buffer::#output_buffer<f32, #(#output_shape),*> {
-                let input = microflow::tensor::#input_tensor::quantize(input, [#(#input_scale),*], [#(#input_zero_point),*]);
-                Self::predict_inner(input).dequantize()
+            /// The size in bytes of the arena holding the intermediate tensors of the model.
+            pub const ARENA_SIZE: usize = #arena_size;
+
+            /// Places the whole arena of [`Self::ARENA_SIZE`] bytes on the stack: stack-constrained
+            /// targets must call [`Self::predict_with_arena()`] with a `static` arena instead.
+            pub fn predict(input: #input_buffer) -> #output_buffer {
+                Self::predict_with_arena(input, &mut microflow::arena::Arena::new())
+            }
+
+            /// Places the whole arena of [`Self::ARENA_SIZE`] bytes on the stack: stack-constrained
+            /// targets must call [`Self::predict_quantized_with_arena()`] with a `static` arena instead.
+            pub fn predict_quantized(input: #input_quantized_buffer) -> #output_buffer {
+                Self::predict_quantized_with_arena(input, &mut microflow::arena::Arena::new())
+            }
+
+            /// Places the whole arena of [`Self::ARENA_SIZE`] bytes on the stack: stack-constrained
+            /// targets must call [`Self::predict_into_with_arena()`] with a `static` arena instead.
+            pub fn predict_into(input: &#input_buffer, output: &mut #output_buffer) {
+                Self::predict_into_with_arena(input, output, &mut microflow::arena::Arena::new())
+            }
+
+            /// Places the whole arena of [`Self::ARENA_SIZE`] bytes on the stack: stack-constrained
+            /// targets must call [`Self::predict_quantized_into_with_arena()`] with a `static` arena instead.
+            pub fn predict_quantized_into(input: &#input_quantized_buffer, output: &mut #output_buffer) {
+                Self::predict_quantized_into_with_arena(input, output, &mut microflow::arena::Arena::new())
+            }
+
+            pub fn predict_with_arena(input: #input_buffer, arena: &mut microflow::arena::Arena<#arena_size>) -> #output_buffer {
+                let arena = &*arena;
+                #(#input_quantize)*
+                #output_dequantize
+            }
+
+            pub fn predict_quantized_with_arena(input: #input_quantized_buffer, arena: &mut microflow::arena::Arena<#arena_size>) -> #output_buffer {
+                let arena = &*arena;
+                #(#input_copy)*
+                #output_dequantize
+            }
+
+            pub fn predict_into_with_arena(input: &#input_buffer, output: &mut #output_buffer, arena: &mut microflow::arena::Arena<#arena_size>) {
+                let arena = &*arena;
+                #(#input_quantize_borrowed)*
+                #output_dequantize_into
             }
 
-            pub fn predict_quantized(input: microflow::buffer::#input_buffer<#input_type, #(#input_shape),*>) -> microflow::buffer::#output_buffer<f32, #(#output_shape),*> {
-                let input = microflow::tensor::#input_tensor::new(input, [#(#input_scale),*], [#(#input_zero_point),*]);
-                Self::predict_inner(input).dequantize()
+            pub fn predict_quantized_into_with_arena(input: &#input_quantized_buffer, output: &mut #output_buffer, arena: &mut microflow::arena::Arena<#arena_size>) {
+                let arena = &*arena;
+                #(#input_copy_borrowed)*
+                #output_dequantize_into
             }
 
-            fn predict_inner(input: microflow::tensor::#input_tensor<#input_type, #(#input_shape),*, 1usize>) -> microflow::tensor::#output_tensor<#output_type, #(#output_shape),*, 1usize> {
+            // The inputs must have been written into their slots of the arena
+            fn predict_inner(arena: &microflow::arena::Arena<#arena_size>) -> #output_tensor {
+                #(#input_slots)*
                 #layers
-                input
+                #output_idents
             }
         }
     };
@@ -206,3 +469,37 @@ pub fn model(args: TokenStream, item: TokenStream) -> TokenStream {
 
     ts.into()
 }
+
+/// Returns the path of the model, resolving the relative paths from the directory of the crate
+/// manifest, i.e., the crate invoking the macro.
+///
+/// # Arguments
+/// * `path` - The path of the model, as given to the macro
+///
+fn model_path(path: &str) -> PathBuf {
+    match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(manifest_dir) => Path::new(&manifest_dir).join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_path_relative() {
+        assert_eq!(
+            model_path("models/sine.tflite"),
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("models/sine.tflite")
+        );
+    }
+
+    #[test]
+    fn model_path_absolute() {
+        assert_eq!(
+            model_path("/path/to/model.tflite"),
+            PathBuf::from("/path/to/model.tflite")
+        );
+    }
+}
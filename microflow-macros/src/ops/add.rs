@@ -0,0 +1,321 @@
+use crate::activation::TokenFusedActivation;
+use crate::quantize::{TokenQuantized, TokenQuantizedMultiplier, TokenRequantization};
+use crate::tensor::{tensor_ident, TokenTensor};
+use crate::tflite_flatbuffers::tflite::{Buffer, Operator, Tensor, TensorType};
+use flatbuffers::{ForwardsUOffset, Vector};
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort_call_site;
+use quote::{quote, ToTokens};
+use simba::scalar::SupersetOf;
+
+/// Represents the tokenized version of the `Add` operator.
+pub(crate) struct TokenAdd<T: TokenQuantized> {
+    pub(crate) constant: Option<TokenTensor<T>>,
+    pub(crate) output: TokenTensor<T>,
+    pub(crate) input_indices: (usize, usize),
+    pub(crate) output_index: usize,
+    pub(crate) fused_activation: TokenFusedActivation<T>,
+    pub(crate) constants: (f32, f32, f32),
+    pub(crate) integer_constants: Option<(
+        TokenQuantizedMultiplier,
+        TokenQuantizedMultiplier,
+        TokenQuantizedMultiplier,
+    )>,
+}
+
+/// Parses the [`TokenAdd`] struct from the given operator.
+///
+/// # Arguments
+/// * `operator` - The model operator as an [`Operator`]
+/// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
+///
+pub(crate) fn parse(
+    operator: Operator,
+    tensors: Vector<ForwardsUOffset<Tensor>>,
+    buffers: Vector<ForwardsUOffset<Buffer>>,
+    requantization: TokenRequantization,
+) -> Box<dyn ToTokens> {
+    let inputs = operator.inputs().unwrap();
+    let input_type = tensors.get(inputs.get(0) as usize).type_();
+    match input_type {
+        TensorType::INT8 => Box::new(TokenAdd::<i8>::new(
+            operator,
+            tensors,
+            buffers,
+            requantization,
+        )),
+        TensorType::UINT8 => Box::new(TokenAdd::<u8>::new(
+            operator,
+            tensors,
+            buffers,
+            requantization,
+        )),
+        input_type => abort_call_site!(
+            "Add supports only INT8/UINT8 input tensors, got {:?}",
+            input_type
+        ),
+    }
+}
+
+impl<T: TokenQuantized> TokenAdd<T> {
+    /// Builds the [`TokenAdd`] operator from the given model operator and tensors.
+    ///
+    /// # Arguments
+    /// * `operator` - The model operator as an [`Operator`]
+    /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
+    /// * `requantization` - The requantization mode as a [`TokenRequantization`]
+    ///
+    pub(crate) fn new(
+        operator: Operator,
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        buffers: Vector<ForwardsUOffset<Buffer>>,
+        requantization: TokenRequantization,
+    ) -> Self {
+        let inputs = operator.inputs().unwrap();
+        let is_constant = |tensor: Tensor| {
+            buffers
+                .get(tensor.buffer() as usize)
+                .data()
+                .is_some_and(|data| !data.is_empty())
+        };
+        // The addition is commutative, so the constant operand (if any) can always be the second one
+        let input_indices = match (inputs.get(0) as usize, inputs.get(1) as usize) {
+            (input_1, input_2) if is_constant(tensors.get(input_1)) => (input_2, input_1),
+            input_indices => input_indices,
+        };
+        if is_constant(tensors.get(input_indices.0)) {
+            abort_call_site!("Add supports at most one constant input tensor");
+        }
+        let output = TokenTensor::from_empty_tensor(
+            tensors.get(operator.outputs().unwrap().get(0) as usize),
+        );
+        let input_1 = TokenTensor::from_empty_tensor(tensors.get(input_indices.0));
+        let (input_2, constant) = if is_constant(tensors.get(input_indices.1)) {
+            let constant = TokenTensor::from_buffered_tensor(
+                tensors.get(input_indices.1),
+                buffers,
+                output.shape().len(),
+            );
+            (None, Some(constant))
+        } else {
+            let input_2 = TokenTensor::from_empty_tensor(tensors.get(input_indices.1));
+            (Some(input_2), None)
+        };
+        let input_2 = input_2.as_ref().or(constant.as_ref()).unwrap();
+        // As in TensorFlow Lite, the shapes of the inputs are padded with leading 1s up to the rank of
+        // the output, and each of their dimensions must match the output one or be 1
+        let broadcastable = |shape: &Vec<usize>| {
+            let padding = output.shape().len().saturating_sub(shape.len());
+            shape.len() <= output.shape().len()
+                && shape
+                    .iter()
+                    .zip(&output.shape()[padding..])
+                    .all(|(&dimension, &size)| dimension == size || dimension == 1)
+        };
+        if !broadcastable(input_1.shape()) || !broadcastable(input_2.shape()) {
+            abort_call_site!(
+                "Add supports only input tensors broadcastable to the output, got shapes {:?} and {:?} with output shape {:?}",
+                input_1.shape(),
+                input_2.shape(),
+                output.shape()
+            );
+        }
+        let options = operator.builtin_options_as_add_options().unwrap();
+        let constants = Self::preprocess(&input_1, input_2, &output);
+        let integer_constants = (requantization == TokenRequantization::Integer)
+            .then(|| Self::preprocess_integer(&input_1, input_2, &output));
+        let fused_activation = TokenFusedActivation::new(
+            options.fused_activation_function(),
+            output.scale()[0],
+            output.zero_point()[0],
+        );
+        Self {
+            constant,
+            output,
+            input_indices,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            fused_activation,
+            constants,
+            integer_constants,
+        }
+    }
+
+    /// Pre-processes the operator, returning the tuple of constants.
+    ///
+    /// # Arguments
+    /// * `input_1` - The first input of the operator as a [`TokenTensor`]
+    /// * `input_2` - The second input of the operator as a [`TokenTensor`]
+    /// * `output` - The output of the operator as a [`TokenTensor`]
+    ///
+    fn preprocess(
+        input_1: &TokenTensor<T>,
+        input_2: &TokenTensor<T>,
+        output: &TokenTensor<T>,
+    ) -> (f32, f32, f32) {
+        (
+            input_1.scale()[0] / output.scale()[0],
+            input_2.scale()[0] / output.scale()[0],
+            f32::from_subset(&output.zero_point()[0])
+                - input_1.scale()[0] * f32::from_subset(&input_1.zero_point()[0])
+                    / output.scale()[0]
+                - input_2.scale()[0] * f32::from_subset(&input_2.zero_point()[0])
+                    / output.scale()[0],
+        )
+    }
+
+    /// Pre-processes the operator for the integer-only requantization, returning the tuple of
+    /// constants.
+    ///
+    /// # Arguments
+    /// * `input_1` - The first input of the operator as a [`TokenTensor`]
+    /// * `input_2` - The second input of the operator as a [`TokenTensor`]
+    /// * `output` - The output of the operator as a [`TokenTensor`]
+    ///
+    fn preprocess_integer(
+        input_1: &TokenTensor<T>,
+        input_2: &TokenTensor<T>,
+        output: &TokenTensor<T>,
+    ) -> (
+        TokenQuantizedMultiplier,
+        TokenQuantizedMultiplier,
+        TokenQuantizedMultiplier,
+    ) {
+        // As in TensorFlow Lite, the inputs are rescaled to twice their maximum scale, and the
+        // sum is then rescaled to the output scale, compensating for the runtime `SUM_LEFT_SHIFT`
+        let scale_1 = input_1.scale()[0] as f64;
+        let scale_2 = input_2.scale()[0] as f64;
+        let twice_max_scale = 2. * scale_1.max(scale_2);
+        (
+            TokenQuantizedMultiplier::new(scale_1 / twice_max_scale),
+            TokenQuantizedMultiplier::new(scale_2 / twice_max_scale),
+            TokenQuantizedMultiplier::new(
+                twice_max_scale / ((1 << 20) as f64 * output.scale()[0] as f64),
+            ),
+        )
+    }
+}
+
+impl<T: TokenQuantized> ToTokens for TokenAdd<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let constant = self.constant.as_ref().map(|constant| {
+            let constant_ident = tensor_ident(self.input_indices.1);
+            let constant_type = constant.type_tokens();
+            quote!(const #constant_ident: #constant_type = #constant;)
+        });
+        let input_1 = tensor_ident(self.input_indices.0);
+        let input_2 = tensor_ident(self.input_indices.1);
+        let input_2 = match self.constant {
+            Some(_) => quote!(&#input_2),
+            None => quote!(#input_2),
+        };
+        let output = tensor_ident(self.output_index);
+        let output_tensor = self.output.kind_tokens();
+        let output_shape = self.output.shape();
+        let output_scale = self.output.scale();
+        let output_zero_point = self.output.zero_point();
+        let fused_activation = &self.fused_activation;
+        let constants = match self.integer_constants {
+            Some((constants_0, constants_1, constants_2)) => {
+                quote!((#constants_0, #constants_1, #constants_2))
+            }
+            None => {
+                let (constants_0, constants_1, constants_2) = self.constants;
+                quote!((#constants_0, #constants_1, #constants_2))
+            }
+        };
+
+        let ts = quote! {
+            #constant
+            let #output: &mut microflow::tensor::#output_tensor<_, #(#output_shape),*, 1usize> =
+                #output;
+            microflow::ops::add(
+                #input_1,
+                #input_2,
+                [#(#output_scale),*],
+                [#(#output_zero_point),*],
+                microflow::ops::AddOptions {
+                    fused_activation: #fused_activation,
+                },
+                #constants,
+                #output
+            );
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::TokenBuffer4D;
+    use crate::tensor::TokenTensor4D;
+    use nalgebra::dmatrix;
+
+    fn setup() -> TokenAdd<i8> {
+        TokenAdd {
+            constant: Some(TokenTensor::Tensor4D(TokenTensor4D {
+                buffer: TokenBuffer4D::from(vec![dmatrix![vec![1, 2]]]),
+                shape: vec![1, 1, 1, 2],
+                scale: vec![0.3],
+                zero_point: vec![4],
+            })),
+            output: TokenTensor::Tensor4D(TokenTensor4D {
+                buffer: TokenBuffer4D::new(),
+                shape: vec![1, 2, 3, 2],
+                scale: vec![0.5],
+                zero_point: vec![6],
+            }),
+            input_indices: (0, 1),
+            output_index: 2,
+            fused_activation: TokenFusedActivation::Relu,
+            constants: (7., 8., 9.),
+            integer_constants: None,
+        }
+    }
+
+    #[test]
+    fn add_preprocess() {
+        let layer = setup();
+        let input = TokenTensor::Tensor4D(TokenTensor4D {
+            buffer: TokenBuffer4D::new(),
+            shape: vec![1, 2, 3, 2],
+            scale: vec![0.1],
+            zero_point: vec![10],
+        });
+        let constants =
+            TokenAdd::preprocess(&input, layer.constant.as_ref().unwrap(), &layer.output);
+        assert_eq!(constants.0, 0.2);
+        assert_eq!(constants.1, 0.6);
+        assert_eq!(constants.2, 1.5999999);
+    }
+
+    #[test]
+    fn add_to_tokens() {
+        let layer = setup();
+        let constant = layer.constant.as_ref().unwrap();
+        let fused_activation = &layer.fused_activation;
+        assert_eq!(
+            layer.to_token_stream().to_string(),
+            quote! {
+                const tensor_1: microflow::tensor::Tensor4D<i8, 1usize, 1usize, 1usize, 2usize, 1usize> = #constant;
+                let tensor_2: &mut microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 2usize, 1usize> =
+                    tensor_2;
+                microflow::ops::add(
+                    tensor_0,
+                    &tensor_1,
+                    [0.5f32],
+                    [6i8],
+                    microflow::ops::AddOptions {
+                        fused_activation: #fused_activation,
+                    },
+                    (7f32, 8f32, 9f32),
+                    tensor_2
+                );
+            }
+            .to_string()
+        );
+    }
+}
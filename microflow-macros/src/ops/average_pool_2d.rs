@@ -1,6 +1,6 @@
 use crate::activation::TokenFusedActivation;
-use crate::quantize::TokenQuantized;
-use crate::tensor::{TokenTensor4D, TokenTensorViewPadding};
+use crate::quantize::{TokenQuantized, TokenQuantizedMultiplier, TokenRequantization};
+use crate::tensor::{tensor_ident, TokenTensor4D, TokenTensorViewPadding};
 use crate::tflite_flatbuffers::tflite::{Operator, Tensor, TensorType};
 use flatbuffers::{ForwardsUOffset, Vector};
 use proc_macro2::TokenStream as TokenStream2;
@@ -12,10 +12,13 @@ use simba::scalar::SupersetOf;
 pub(crate) struct TokenAveragePool2D<T: TokenQuantized> {
     pub(crate) filter_shape: (usize, usize),
     pub(crate) output: TokenTensor4D<T>,
-    pub(crate) fused_activation: TokenFusedActivation,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) fused_activation: TokenFusedActivation<T>,
     pub(crate) view_padding: TokenTensorViewPadding,
     pub(crate) strides: (usize, usize),
     pub(crate) constants: (f32, f32),
+    pub(crate) integer_constants: Option<(TokenQuantizedMultiplier, i32)>,
 }
 
 /// Parses the [`TokenAveragePool2D`] struct from the given operator.
@@ -23,16 +26,26 @@ pub(crate) struct TokenAveragePool2D<T: TokenQuantized> {
 /// # Arguments
 /// * `operator` - The model operator as an [`Operator`]
 /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
 ///
 pub(crate) fn parse(
     operator: Operator,
     tensors: Vector<ForwardsUOffset<Tensor>>,
+    requantization: TokenRequantization,
 ) -> Box<dyn ToTokens> {
     let inputs = operator.inputs().unwrap();
     let input_type = tensors.get(inputs.get(0) as usize).type_();
     match input_type {
-        TensorType::INT8 => Box::new(TokenAveragePool2D::<i8>::new(operator, tensors)),
-        TensorType::UINT8 => Box::new(TokenAveragePool2D::<u8>::new(operator, tensors)),
+        TensorType::INT8 => Box::new(TokenAveragePool2D::<i8>::new(
+            operator,
+            tensors,
+            requantization,
+        )),
+        TensorType::UINT8 => Box::new(TokenAveragePool2D::<u8>::new(
+            operator,
+            tensors,
+            requantization,
+        )),
         input_type => abort_call_site!(
             "AveragePool2D supports only INT8/UINT8 input tensors, got {:?}",
             input_type
@@ -46,8 +59,13 @@ impl<T: TokenQuantized> TokenAveragePool2D<T> {
     /// # Arguments
     /// * `operator` - The model operator as an [`Operator`]
     /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `requantization` - The requantization mode as a [`TokenRequantization`]
     ///
-    pub(crate) fn new(operator: Operator, tensors: Vector<ForwardsUOffset<Tensor>>) -> Self {
+    pub(crate) fn new(
+        operator: Operator,
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        requantization: TokenRequantization,
+    ) -> Self {
         let inputs = operator.inputs().unwrap();
         let input = TokenTensor4D::from_empty_tensor(tensors.get(inputs.get(0) as usize));
         let output = TokenTensor4D::from_empty_tensor(
@@ -55,16 +73,26 @@ impl<T: TokenQuantized> TokenAveragePool2D<T> {
         );
         let options = operator.builtin_options_as_pool_2_doptions().unwrap();
         let constants = Self::preprocess(&input, &output);
+        let integer_constants = (requantization == TokenRequantization::Integer)
+            .then(|| Self::preprocess_integer(&input, &output));
+        let fused_activation = TokenFusedActivation::new(
+            options.fused_activation_function(),
+            output.scale[0],
+            output.zero_point[0],
+        );
         Self {
             filter_shape: (
                 options.filter_height() as usize,
                 options.filter_width() as usize,
             ),
             output,
-            fused_activation: options.fused_activation_function().into(),
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            fused_activation,
             view_padding: options.padding().into(),
             strides: (options.stride_h() as usize, options.stride_w() as usize),
             constants,
+            integer_constants,
         }
     }
 
@@ -81,6 +109,23 @@ impl<T: TokenQuantized> TokenAveragePool2D<T> {
                 - (input.scale[0] * f32::from_subset(&input.zero_point[0])) / output.scale[0],
         )
     }
+
+    /// Pre-processes the operator for the integer-only requantization, returning the tuple of
+    /// constants.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor2D`]
+    /// * `output` - The output of the operator as a [`TokenTensor2D`]
+    ///
+    fn preprocess_integer(
+        input: &TokenTensor4D<T>,
+        output: &TokenTensor4D<T>,
+    ) -> (TokenQuantizedMultiplier, i32) {
+        (
+            TokenQuantizedMultiplier::new(input.scale[0] as f64 / output.scale[0] as f64),
+            -i32::from_subset(&input.zero_point[0]),
+        )
+    }
 }
 
 impl<T: TokenQuantized> ToTokens for TokenAveragePool2D<T> {
@@ -89,24 +134,33 @@ impl<T: TokenQuantized> ToTokens for TokenAveragePool2D<T> {
         let output_shape = &self.output.shape;
         let output_scale = &self.output.scale;
         let output_zero_point = &self.output.zero_point;
-        let fused_activation = self.fused_activation;
+        let fused_activation = &self.fused_activation;
         let view_padding = self.view_padding;
         let (strides_0, strides_1) = self.strides;
-        let (constants_0, constants_1) = self.constants;
+        let constants = match self.integer_constants {
+            Some((constants_0, constants_1)) => quote!((#constants_0, #constants_1)),
+            None => {
+                let (constants_0, constants_1) = self.constants;
+                quote!((#constants_0, #constants_1))
+            }
+        };
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
 
         let ts = quote! {
-            let input: microflow::tensor::Tensor4D<_, #(#output_shape),*, 1usize> =
-                microflow::ops::average_pool_2d(
-                    input,
-                    (nalgebra::Const::<#filter_shape_0>, nalgebra::Const::<#filter_shape_1>),
-                    [#(#output_scale),*],
-                    [#(#output_zero_point),*],
-                    microflow::ops::AveragePool2DOptions {
-                        fused_activation: #fused_activation,
-                        view_padding: #view_padding,
-                        strides: (#strides_0, #strides_1),
-                    },
-                    (#constants_0, #constants_1)
+            let #output: &mut microflow::tensor::Tensor4D<_, #(#output_shape),*, 1usize> = #output;
+            microflow::ops::average_pool_2d(
+                #input,
+                (nalgebra::Const::<#filter_shape_0>, nalgebra::Const::<#filter_shape_1>),
+                [#(#output_scale),*],
+                [#(#output_zero_point),*],
+                microflow::ops::AveragePool2DOptions {
+                    fused_activation: #fused_activation,
+                    view_padding: #view_padding,
+                    strides: (#strides_0, #strides_1),
+                },
+                #constants,
+                #output
             );
         };
         ts.to_tokens(tokens);
@@ -127,10 +181,13 @@ mod tests {
                 scale: vec![0.1],
                 zero_point: vec![2],
             },
+            input_index: 0,
+            output_index: 1,
             fused_activation: TokenFusedActivation::None,
             view_padding: TokenTensorViewPadding::Same,
             strides: (1, 1),
             constants: (3., 4.),
+            integer_constants: None,
         }
     }
 
@@ -151,23 +208,25 @@ mod tests {
     #[test]
     fn average_pool_2d_to_tokens() {
         let layer = setup();
-        let fused_activation = layer.fused_activation;
+        let fused_activation = &layer.fused_activation;
         let view_padding = layer.view_padding;
         assert_eq!(
             layer.to_token_stream().to_string(),
             quote! {
-                let input: microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 2usize, 1usize> =
-                    microflow::ops::average_pool_2d(
-                        input,
-                        (nalgebra::Const::<2usize>, nalgebra::Const::<3usize>),
-                        [0.1f32],
-                        [2i8],
-                        microflow::ops::AveragePool2DOptions {
-                            fused_activation: #fused_activation,
-                            view_padding: #view_padding,
-                            strides: (1usize, 1usize),
-                        },
-                        (3f32, 4f32)
+                let tensor_1: &mut microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 2usize, 1usize> =
+                    tensor_1;
+                microflow::ops::average_pool_2d(
+                    tensor_0,
+                    (nalgebra::Const::<2usize>, nalgebra::Const::<3usize>),
+                    [0.1f32],
+                    [2i8],
+                    microflow::ops::AveragePool2DOptions {
+                        fused_activation: #fused_activation,
+                        view_padding: #view_padding,
+                        strides: (1usize, 1usize),
+                    },
+                    (3f32, 4f32),
+                    tensor_1
                 );
             }
             .to_string()
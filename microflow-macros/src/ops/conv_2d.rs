@@ -1,7 +1,7 @@
 use crate::activation::TokenFusedActivation;
-use crate::buffer::TokenBuffer2D;
-use crate::quantize::TokenQuantized;
-use crate::tensor::{TokenTensor2D, TokenTensor4D, TokenTensorViewPadding};
+use crate::buffer::{TokenBuffer2D, TokenBufferEmission};
+use crate::quantize::{TokenQuantized, TokenQuantizedMultiplier, TokenRequantization};
+use crate::tensor::{tensor_ident, TokenTensor2D, TokenTensor4D, TokenTensorViewPadding};
 use crate::tflite_flatbuffers::tflite::{Buffer, Operator, Tensor, TensorType};
 use flatbuffers::{ForwardsUOffset, Vector};
 use nalgebra::DMatrix;
@@ -13,11 +13,17 @@ use quote::{format_ident, quote, ToTokens};
 pub(crate) struct TokenConv2D<T: TokenQuantized> {
     pub(crate) filters: TokenTensor4D<T>,
     pub(crate) output: TokenTensor4D<T>,
-    pub(crate) fused_activation: TokenFusedActivation,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) fused_activation: TokenFusedActivation<T>,
     pub(crate) view_padding: TokenTensorViewPadding,
     pub(crate) strides: (usize, usize),
+    pub(crate) dilations: (usize, usize),
     pub(crate) constants: (TokenBuffer2D<f32>, TokenBuffer2D<f32>),
+    pub(crate) integer_constants:
+        Option<(TokenBuffer2D<i32>, TokenBuffer2D<TokenQuantizedMultiplier>)>,
     pub(crate) index: usize,
+    pub(crate) emission: TokenBufferEmission,
 }
 
 /// Parses the [`TokenConv2D`] struct from the given operator.
@@ -27,18 +33,36 @@ pub(crate) struct TokenConv2D<T: TokenQuantized> {
 /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
 /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
 /// * `index` - The operator index
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
+/// * `emission` - The emission mode of the filters as a [`TokenBufferEmission`]
 ///
 pub(crate) fn parse(
     operator: Operator,
     tensors: Vector<ForwardsUOffset<Tensor>>,
     buffers: Vector<ForwardsUOffset<Buffer>>,
     index: usize,
+    requantization: TokenRequantization,
+    emission: TokenBufferEmission,
 ) -> Box<dyn ToTokens> {
     let inputs = operator.inputs().unwrap();
     let input_type = tensors.get(inputs.get(0) as usize).type_();
     match input_type {
-        TensorType::INT8 => Box::new(TokenConv2D::<i8>::new(operator, tensors, buffers, index)),
-        TensorType::UINT8 => Box::new(TokenConv2D::<u8>::new(operator, tensors, buffers, index)),
+        TensorType::INT8 => Box::new(TokenConv2D::<i8>::new(
+            operator,
+            tensors,
+            buffers,
+            index,
+            requantization,
+            emission,
+        )),
+        TensorType::UINT8 => Box::new(TokenConv2D::<u8>::new(
+            operator,
+            tensors,
+            buffers,
+            index,
+            requantization,
+            emission,
+        )),
         input_type => abort_call_site!(
             "Conv2D supports only INT8/UINT8 input tensors, got {:?}",
             input_type
@@ -54,32 +78,50 @@ impl<T: TokenQuantized> TokenConv2D<T> {
     /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
     /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
     /// * `index` - The operator index
+    /// * `requantization` - The requantization mode as a [`TokenRequantization`]
+    /// * `emission` - The emission mode of the filters as a [`TokenBufferEmission`]
     ///
     pub(crate) fn new(
         operator: Operator,
         tensors: Vector<ForwardsUOffset<Tensor>>,
         buffers: Vector<ForwardsUOffset<Buffer>>,
         index: usize,
+        requantization: TokenRequantization,
+        emission: TokenBufferEmission,
     ) -> Self {
         let inputs = operator.inputs().unwrap();
         let input = TokenTensor4D::from_empty_tensor(tensors.get(inputs.get(0) as usize));
         let filters =
             TokenTensor4D::from_buffered_tensor(tensors.get(inputs.get(1) as usize), buffers);
-        let biases =
-            TokenTensor2D::from_buffered_tensor(tensors.get(inputs.get(2) as usize), buffers);
+        let biases = TokenTensor2D::from_bias(operator, tensors, buffers, filters.shape[0]);
         let output = TokenTensor4D::from_empty_tensor(
             tensors.get(operator.outputs().unwrap().get(0) as usize),
         );
         let options = operator.builtin_options_as_conv_2_doptions().unwrap();
         let constants = Self::preprocess(&input, &filters, &biases, &output);
+        let integer_constants = (requantization == TokenRequantization::Integer)
+            .then(|| Self::preprocess_integer(&input, &filters, &biases, &output));
+        let fused_activation = TokenFusedActivation::new(
+            options.fused_activation_function(),
+            output.scale[0],
+            output.zero_point[0],
+        );
         Self {
             filters,
             output,
-            fused_activation: options.fused_activation_function().into(),
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            fused_activation,
             view_padding: options.padding().into(),
             strides: (options.stride_h() as usize, options.stride_w() as usize),
+            dilations: (
+                options.dilation_h_factor() as usize,
+                options.dilation_w_factor() as usize,
+            ),
             constants,
+            integer_constants,
             index,
+            emission,
         }
     }
 
@@ -112,35 +154,79 @@ impl<T: TokenQuantized> TokenConv2D<T> {
             })),
         )
     }
+
+    /// Pre-processes the operator for the integer-only requantization, returning the tuple of
+    /// constants.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor2D`]
+    /// * `filters` - The filters of the operator as a [`TokenTensor2D`]
+    /// * `biases` - The biases of the operator as a [`TokenTensor2D`]
+    /// * `output` - The output of the operator as a [`TokenTensor2D`]
+    ///
+    fn preprocess_integer(
+        input: &TokenTensor4D<T>,
+        filters: &TokenTensor4D<T>,
+        biases: &TokenTensor2D<i32>,
+        output: &TokenTensor4D<T>,
+    ) -> (TokenBuffer2D<i32>, TokenBuffer2D<TokenQuantizedMultiplier>) {
+        (
+            TokenBuffer2D::from(DMatrix::from_fn(filters.shape[0], 1, |b, _| {
+                // As in TensorFlow Lite, the biases are expected in the scale of the accumulators
+                biases.buffer[b]
+                    - biases
+                        .zero_point
+                        .get(b)
+                        .copied()
+                        .unwrap_or(biases.zero_point[0])
+            })),
+            TokenBuffer2D::from(DMatrix::from_fn(filters.scale.len(), 1, |b, _| {
+                TokenQuantizedMultiplier::new(
+                    input.scale[0] as f64 * filters.scale[b] as f64 / output.scale[0] as f64,
+                )
+            })),
+        )
+    }
 }
 
 impl<T: TokenQuantized> ToTokens for TokenConv2D<T> {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
         let filters_ident = format_ident!("filters_{}", self.index);
         let filters_type = self.filters.type_tokens();
-        let filters = &self.filters;
+        let filters = self.filters.emit(self.emission);
         let output_shape = &self.output.shape;
         let output_scale = &self.output.scale;
         let output_zero_point = &self.output.zero_point;
-        let fused_activation = self.fused_activation;
+        let fused_activation = &self.fused_activation;
         let view_padding = self.view_padding;
         let (strides_0, strides_1) = self.strides;
-        let (constants_0, constants_1) = &self.constants;
+        let (dilations_0, dilations_1) = self.dilations;
+        let constants = match &self.integer_constants {
+            Some((constants_0, constants_1)) => quote!((#constants_0, #constants_1)),
+            None => {
+                let (constants_0, constants_1) = &self.constants;
+                quote!((#constants_0, #constants_1))
+            }
+        };
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
 
         let ts = quote! {
             const #filters_ident: #filters_type = #filters;
-            let input: microflow::tensor::Tensor4D<_, #(#output_shape),*, 1usize> =
-                microflow::ops::conv_2d(
-                    input,
-                    &#filters_ident,
-                    [#(#output_scale),*],
-                    [#(#output_zero_point),*],
-                    microflow::ops::Conv2DOptions {
-                        fused_activation: #fused_activation,
-                        view_padding: #view_padding,
-                        strides: (#strides_0, #strides_1),
-                    },
-                    (#constants_0, #constants_1)
+            let #output: &mut microflow::tensor::Tensor4D<_, #(#output_shape),*, 1usize> = #output;
+            microflow::ops::conv_2d(
+                #input,
+                &#filters_ident,
+                [#(#output_scale),*],
+                [#(#output_zero_point),*],
+                microflow::ops::Conv2DOptions {
+                    fused_activation: #fused_activation,
+                    view_padding: #view_padding,
+                    strides: (#strides_0, #strides_1),
+                    dilations: (#dilations_0, #dilations_1),
+                },
+                #constants,
+                #output
             );
         };
         ts.to_tokens(tokens);
@@ -176,14 +262,19 @@ mod tests {
                 scale: vec![0.29],
                 zero_point: vec![30],
             },
-            fused_activation: TokenFusedActivation::Relu6,
+            input_index: 0,
+            output_index: 1,
+            fused_activation: TokenFusedActivation::Relu6(127),
             view_padding: TokenTensorViewPadding::Same,
             strides: (1, 1),
+            dilations: (1, 2),
             constants: (
                 TokenBuffer2D::from(dmatrix![31., 32.]),
                 TokenBuffer2D::from(dmatrix![33., 34.]),
             ),
+            integer_constants: None,
             index: 0,
+            emission: TokenBufferEmission::Matrix,
         }
     }
 
@@ -214,25 +305,28 @@ mod tests {
     fn conv_2d_to_tokens() {
         let layer = setup();
         let filters = &layer.filters;
-        let fused_activation = layer.fused_activation;
+        let fused_activation = &layer.fused_activation;
         let view_padding = layer.view_padding;
         let (constants_0, constants_1) = &layer.constants;
         assert_eq!(
             layer.to_token_stream().to_string(),
             quote! {
                 const filters_0: microflow::tensor::Tensor4D<i8, 2usize, 2usize, 3usize, 2usize, 2usize> = #filters;
-                let input: microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 2usize, 1usize> =
-                    microflow::ops::conv_2d(
-                        input,
-                        &filters_0,
-                        [0.29f32],
-                        [30i8],
-                        microflow::ops::Conv2DOptions {
-                            fused_activation: #fused_activation,
-                            view_padding: #view_padding,
-                            strides: (1usize, 1usize),
-                        },
-                        (#constants_0, #constants_1)
+                let tensor_1: &mut microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 2usize, 1usize> =
+                    tensor_1;
+                microflow::ops::conv_2d(
+                    tensor_0,
+                    &filters_0,
+                    [0.29f32],
+                    [30i8],
+                    microflow::ops::Conv2DOptions {
+                        fused_activation: #fused_activation,
+                        view_padding: #view_padding,
+                        strides: (1usize, 1usize),
+                        dilations: (1usize, 2usize),
+                    },
+                    (#constants_0, #constants_1),
+                    tensor_1
                 );
             }.to_string()
         );
@@ -6,19 +6,35 @@ use quote::{format_ident, quote, ToTokens};
 use simba::scalar::SupersetOf;
 
 use crate::activation::TokenFusedActivation;
-use crate::buffer::TokenBuffer2D;
-use crate::quantize::TokenQuantized;
-use crate::tensor::TokenTensor2D;
+use crate::buffer::{TokenBuffer2D, TokenBufferEmission};
+use crate::quantize::{TokenQuantized, TokenQuantizedMultiplier, TokenRequantization};
+use crate::tensor::{tensor_ident, TokenTensor2D};
 use crate::tflite_flatbuffers::tflite::{Buffer, Operator, Tensor, TensorType};
 
+/// Represents the constants of the `FullyConnected` operator for the integer-only requantization.
+type TokenIntegerConstants = (
+    TokenBuffer2D<i32>,
+    TokenBuffer2D<TokenQuantizedMultiplier>,
+    TokenBuffer2D<i32>,
+    i32,
+);
+
 /// Represents the tokenized version of the `FullyConnected` operator.
 pub(crate) struct TokenFullyConnected<T: TokenQuantized> {
     pub(crate) weights: TokenTensor2D<T>,
     pub(crate) output: TokenTensor2D<T>,
-    pub(crate) fused_activation: TokenFusedActivation,
-    pub(crate) constants: (TokenBuffer2D<f32>, f32, TokenBuffer2D<i32>, i32),
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) fused_activation: TokenFusedActivation<T>,
+    pub(crate) constants: (
+        TokenBuffer2D<f32>,
+        TokenBuffer2D<f32>,
+        TokenBuffer2D<i32>,
+        i32,
+    ),
+    pub(crate) integer_constants: Option<TokenIntegerConstants>,
     pub(crate) index: usize,
-    pub(crate) reshape: bool,
+    pub(crate) emission: TokenBufferEmission,
 }
 
 /// Parses the [`TokenFullyConnected`] struct from the given operator.
@@ -28,21 +44,35 @@ pub(crate) struct TokenFullyConnected<T: TokenQuantized> {
 /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
 /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
 /// * `index` - The operator index
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
+/// * `emission` - The emission mode of the weights as a [`TokenBufferEmission`]
 ///
 pub(crate) fn parse(
     operator: Operator,
     tensors: Vector<ForwardsUOffset<Tensor>>,
     buffers: Vector<ForwardsUOffset<Buffer>>,
     index: usize,
+    requantization: TokenRequantization,
+    emission: TokenBufferEmission,
 ) -> Box<dyn ToTokens> {
     let inputs = operator.inputs().unwrap();
     let input_type = tensors.get(inputs.get(0) as usize).type_();
     match input_type {
         TensorType::INT8 => Box::new(TokenFullyConnected::<i8>::new(
-            operator, tensors, buffers, index,
+            operator,
+            tensors,
+            buffers,
+            index,
+            requantization,
+            emission,
         )),
         TensorType::UINT8 => Box::new(TokenFullyConnected::<u8>::new(
-            operator, tensors, buffers, index,
+            operator,
+            tensors,
+            buffers,
+            index,
+            requantization,
+            emission,
         )),
         input_type => abort_call_site!(
             "FullyConnected supports only INT8/UINT8 input tensors, got {:?}",
@@ -59,19 +89,22 @@ impl<T: TokenQuantized> TokenFullyConnected<T> {
     /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
     /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
     /// * `index` - The operator index
+    /// * `requantization` - The requantization mode as a [`TokenRequantization`]
+    /// * `emission` - The emission mode of the weights as a [`TokenBufferEmission`]
     ///
     pub(crate) fn new(
         operator: Operator,
         tensors: Vector<ForwardsUOffset<Tensor>>,
         buffers: Vector<ForwardsUOffset<Buffer>>,
         index: usize,
+        requantization: TokenRequantization,
+        emission: TokenBufferEmission,
     ) -> Self {
         let inputs = operator.inputs().unwrap();
         let input = TokenTensor2D::from_empty_tensor(tensors.get(inputs.get(0) as usize));
         let weights =
             TokenTensor2D::from_buffered_tensor(tensors.get(inputs.get(1) as usize), buffers);
-        let biases =
-            TokenTensor2D::from_buffered_tensor(tensors.get(inputs.get(2) as usize), buffers);
+        let biases = TokenTensor2D::from_bias(operator, tensors, buffers, weights.shape[1]);
         let output = TokenTensor2D::from_empty_tensor(
             tensors.get(operator.outputs().unwrap().get(0) as usize),
         );
@@ -79,13 +112,23 @@ impl<T: TokenQuantized> TokenFullyConnected<T> {
             .builtin_options_as_fully_connected_options()
             .unwrap();
         let constants = Self::preprocess(&input, &weights, &biases, &output);
+        let integer_constants = (requantization == TokenRequantization::Integer)
+            .then(|| Self::preprocess_integer(&input, &weights, &biases, &output));
+        let fused_activation = TokenFusedActivation::new(
+            options.fused_activation_function(),
+            output.scale[0],
+            output.zero_point[0],
+        );
         Self {
             weights,
             output,
-            fused_activation: options.fused_activation_function().into(),
-            reshape: input.shape.len() != 2,
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            fused_activation,
             constants,
+            integer_constants,
             index,
+            emission,
         }
     }
 
@@ -102,55 +145,105 @@ impl<T: TokenQuantized> TokenFullyConnected<T> {
         weights: &TokenTensor2D<T>,
         biases: &TokenTensor2D<i32>,
         output: &TokenTensor2D<T>,
-    ) -> (TokenBuffer2D<f32>, f32, TokenBuffer2D<i32>, i32) {
+    ) -> (
+        TokenBuffer2D<f32>,
+        TokenBuffer2D<f32>,
+        TokenBuffer2D<i32>,
+        i32,
+    ) {
         (
-            TokenBuffer2D::from(
-                biases.scale[0] / output.scale[0]
-                    * biases
-                        .buffer
-                        .add_scalar(-biases.zero_point[0])
-                        .cast::<f32>(),
-            ),
-            input.scale[0] * weights.scale[0] / output.scale[0],
+            TokenBuffer2D::from(DMatrix::from_fn(weights.shape[1], 1, |c, _| {
+                biases.scale.get(c).copied().unwrap_or(biases.scale[0]) / output.scale[0]
+                    * (biases.buffer[c]
+                        - biases
+                            .zero_point
+                            .get(c)
+                            .copied()
+                            .unwrap_or(biases.zero_point[0])) as f32
+            })),
+            TokenBuffer2D::from(DMatrix::from_fn(weights.scale.len(), 1, |c, _| {
+                input.scale[0] * weights.scale[c] / output.scale[0]
+            })),
             TokenBuffer2D::from(DMatrix::from_rows(&[
                 convert_ref::<DMatrix<T>, DMatrix<i32>>(&weights.buffer).row_sum()
                     * i32::from_subset(&input.zero_point[0]),
             ])),
-            input.shape[1] as i32
-                * i32::from_subset(&input.zero_point[0])
-                * i32::from_subset(&weights.zero_point[0]),
+            input.shape[1] as i32 * i32::from_subset(&input.zero_point[0]),
+        )
+    }
+
+    /// Pre-processes the operator for the integer-only requantization, returning the tuple of
+    /// constants.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor2D`]
+    /// * `weights` - The weights of the operator as a [`TokenTensor2D`]
+    /// * `biases` - The biases of the operator as a [`TokenTensor2D`]
+    /// * `output` - The output of the operator as a [`TokenTensor2D`]
+    ///
+    fn preprocess_integer(
+        input: &TokenTensor2D<T>,
+        weights: &TokenTensor2D<T>,
+        biases: &TokenTensor2D<i32>,
+        output: &TokenTensor2D<T>,
+    ) -> TokenIntegerConstants {
+        let (_, _, constants_2, constants_3) = Self::preprocess(input, weights, biases, output);
+        (
+            // As in TensorFlow Lite, the biases are expected in the scale of the accumulators
+            TokenBuffer2D::from(DMatrix::from_fn(weights.shape[1], 1, |c, _| {
+                biases.buffer[c]
+                    - biases
+                        .zero_point
+                        .get(c)
+                        .copied()
+                        .unwrap_or(biases.zero_point[0])
+            })),
+            // TensorFlow Lite computes the product of the input and weights scales in single precision
+            TokenBuffer2D::from(DMatrix::from_fn(weights.scale.len(), 1, |c, _| {
+                TokenQuantizedMultiplier::new(
+                    (input.scale[0] * weights.scale[c]) as f64 / output.scale[0] as f64,
+                )
+            })),
+            constants_2,
+            constants_3,
         )
     }
 }
 
 impl<T: TokenQuantized> ToTokens for TokenFullyConnected<T> {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let reshape = if self.reshape {
-            quote!(.into())
-        } else {
-            quote!()
-        };
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
         let weights_ident = format_ident!("weights_{}", self.index);
         let weights_type = self.weights.type_tokens();
-        let weights = &self.weights;
+        let weights = self.weights.emit(self.emission);
         let output_shape = &self.output.shape;
         let output_scale = self.output.scale[0];
         let output_zero_point = self.output.zero_point[0];
-        let fused_activation = self.fused_activation;
-        let (constants_0, constants_1, constants_2, constants_3) = &self.constants;
+        let fused_activation = &self.fused_activation;
+        let constants = match &self.integer_constants {
+            Some((constants_0, constants_1, constants_2, constants_3)) => {
+                quote!((#constants_0, #constants_1, #constants_2, #constants_3))
+            }
+            None => {
+                let (constants_0, constants_1, constants_2, constants_3) = &self.constants;
+                quote!((#constants_0, #constants_1, #constants_2, #constants_3))
+            }
+        };
 
         let ts = quote! {
             const #weights_ident: #weights_type = #weights;
-            let input: microflow::tensor::Tensor2D<_, #(#output_shape),*, 1usize> =
-                microflow::ops::fully_connected(
-                    input #reshape,
-                    &#weights_ident,
-                    [#output_scale],
-                    [#output_zero_point],
-                    microflow::ops::FullyConnectedOptions {
-                        fused_activation: #fused_activation,
-                    },
-                    (#constants_0, #constants_1, #constants_2, #constants_3)
+            let #output: &mut microflow::tensor::Tensor2D<_, #(#output_shape),*, 1usize> = #output;
+            microflow::ops::fully_connected(
+                #input,
+                &#weights_ident,
+                [#output_scale],
+                [#output_zero_point],
+                microflow::ops::FullyConnectedOptions {
+                    fused_activation: #fused_activation,
+                },
+                #constants,
+                #output
             );
         };
         ts.to_tokens(tokens);
@@ -180,15 +273,18 @@ mod tests {
                 scale: vec![0.9],
                 zero_point: vec![10],
             },
+            input_index: 0,
+            output_index: 1,
             fused_activation: TokenFusedActivation::Relu,
             constants: (
                 TokenBuffer2D::from(dmatrix![11., 12.]),
-                13.,
+                TokenBuffer2D::from(dmatrix![13.]),
                 TokenBuffer2D::from(dmatrix![14, 15]),
                 16,
             ),
+            integer_constants: None,
             index: 0,
-            reshape: false,
+            emission: TokenBufferEmission::Matrix,
         }
     }
 
@@ -217,32 +313,66 @@ mod tests {
             constants.0 .0,
             Some(dmatrix![-0.9777778; -0.73333335; -0.4888889])
         );
-        assert_eq!(constants.1, 0.13222224);
+        assert_eq!(constants.1 .0, Some(dmatrix![0.13222224]));
         assert_eq!(constants.2 .0, Some(dmatrix![90, 126, 162]));
-        assert_eq!(constants.3, 288);
+        assert_eq!(constants.3, 36);
+    }
+
+    #[test]
+    fn fully_connected_preprocess_per_channel() {
+        let mut layer = setup();
+        layer.weights.scale = vec![0.7, 0.8, 0.9];
+        layer.weights.zero_point = vec![0, 0, 0];
+        let input = TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![1, 2],
+            scale: vec![0.17],
+            zero_point: vec![18],
+        };
+        let biases = TokenTensor2D {
+            buffer: TokenBuffer2D::from(dmatrix![
+                19;
+                20;
+                21
+            ]),
+            shape: vec![3, 1],
+            scale: vec![0.119, 0.136, 0.153],
+            zero_point: vec![0, 0, 0],
+        };
+        let constants =
+            TokenFullyConnected::preprocess(&input, &layer.weights, &biases, &layer.output);
+        assert_eq!(constants.0 .0, Some(dmatrix![2.5122225; 3.0222225; 3.57]));
+        assert_eq!(constants.1 .0, Some(dmatrix![0.13222224; 0.15111113; 0.17]));
+        let integer_constants =
+            TokenFullyConnected::preprocess_integer(&input, &layer.weights, &biases, &layer.output);
+        assert_eq!(integer_constants.0 .0, Some(dmatrix![19; 20; 21]));
+        assert_eq!(integer_constants.1 .0.unwrap().len(), 3);
     }
 
     #[test]
     fn fully_connected_to_tokens() {
         let layer = setup();
         let weights = &layer.weights;
-        let fused_activation = layer.fused_activation;
+        let fused_activation = &layer.fused_activation;
         let constants_0 = &layer.constants.0;
+        let constants_1 = &layer.constants.1;
         let constants_2 = &layer.constants.2;
         assert_eq!(
             layer.to_token_stream().to_string(),
             quote! {
                 const weights_0: microflow::tensor::Tensor2D<i8, 2usize, 3usize, 1usize> = #weights;
-                let input: microflow::tensor::Tensor2D<_, 1usize, 3usize, 1usize> =
-                    microflow::ops::fully_connected(
-                        input,
-                        &weights_0,
-                        [0.9f32],
-                        [10i8],
-                        microflow::ops::FullyConnectedOptions {
-                            fused_activation: #fused_activation,
-                        },
-                        (#constants_0, 13f32, #constants_2, 16i32)
+                let tensor_1: &mut microflow::tensor::Tensor2D<_, 1usize, 3usize, 1usize> =
+                    tensor_1;
+                microflow::ops::fully_connected(
+                    tensor_0,
+                    &weights_0,
+                    [0.9f32],
+                    [10i8],
+                    microflow::ops::FullyConnectedOptions {
+                        fused_activation: #fused_activation,
+                    },
+                    (#constants_0, #constants_1, #constants_2, 16i32),
+                    tensor_1
                 );
             }
             .to_string()
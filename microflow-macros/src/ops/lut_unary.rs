@@ -0,0 +1,339 @@
+use crate::quantize::{lookup_table, TokenQuantized};
+use crate::tensor::{tensor_ident, TokenTensor};
+use crate::tflite_flatbuffers::tflite::{BuiltinOperator, Operator, Tensor, TensorType};
+use flatbuffers::{ForwardsUOffset, Vector};
+use libm::erf;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote, ToTokens};
+use std::any::type_name;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_2_PI};
+use syn::{parse_str, Type};
+
+/// Represents the elementwise unary functions computed with a lookup table.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum TokenUnaryFunction {
+    Logistic,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    Rsqrt,
+    Abs,
+    Neg,
+    Floor,
+    Elu,
+    Gelu { approximate: bool },
+    HardSwish,
+}
+
+impl TokenUnaryFunction {
+    /// Builds the [`TokenUnaryFunction`] from the given builtin operator.
+    ///
+    /// # Arguments
+    /// * `code` - The builtin operator as a [`BuiltinOperator`]
+    /// * `operator` - The model operator as an [`Operator`]
+    ///
+    pub(crate) fn new(code: BuiltinOperator, operator: Operator) -> Self {
+        match code {
+            BuiltinOperator::LOGISTIC => Self::Logistic,
+            BuiltinOperator::TANH => Self::Tanh,
+            BuiltinOperator::EXP => Self::Exp,
+            BuiltinOperator::LOG => Self::Log,
+            BuiltinOperator::SQRT => Self::Sqrt,
+            BuiltinOperator::RSQRT => Self::Rsqrt,
+            BuiltinOperator::ABS => Self::Abs,
+            BuiltinOperator::NEG => Self::Neg,
+            BuiltinOperator::FLOOR => Self::Floor,
+            BuiltinOperator::ELU => Self::Elu,
+            // The missing options default to the exact function, as in TensorFlow Lite
+            BuiltinOperator::GELU => Self::Gelu {
+                approximate: operator
+                    .builtin_options_as_gelu_options()
+                    .is_some_and(|options| options.approximate()),
+            },
+            BuiltinOperator::HARD_SWISH => Self::HardSwish,
+            code => abort_call_site!("{:?} is not an elementwise unary operator", code),
+        }
+    }
+
+    /// Evaluates the function on the given value.
+    ///
+    /// # Arguments
+    /// * `x` - The dequantized input value
+    ///
+    pub(crate) fn evaluate(&self, x: f64) -> f64 {
+        match self {
+            Self::Logistic => 1. / (1. + (-x).exp()),
+            Self::Tanh => x.tanh(),
+            Self::Exp => x.exp(),
+            Self::Log => x.ln(),
+            Self::Sqrt => x.sqrt(),
+            Self::Rsqrt => 1. / x.sqrt(),
+            Self::Abs => x.abs(),
+            Self::Neg => -x,
+            Self::Floor => x.floor(),
+            Self::Elu => {
+                if x > 0. {
+                    x
+                } else {
+                    x.exp_m1()
+                }
+            }
+            Self::Gelu { approximate: false } => 0.5 * x * (1. + erf(x * FRAC_1_SQRT_2)),
+            Self::Gelu { approximate: true } => {
+                0.5 * x * (1. + (FRAC_2_PI.sqrt() * (x + 0.044715 * x.powi(3))).tanh())
+            }
+            Self::HardSwish => x * (x + 3.).clamp(0., 6.) / 6.,
+        }
+    }
+}
+
+/// Represents the tokenized version of the elementwise unary operators, computed with a lookup
+/// table.
+pub(crate) struct TokenLutUnary<T: TokenQuantized> {
+    pub(crate) output: TokenTensor<T>,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) constants: Vec<T>,
+    pub(crate) index: usize,
+}
+
+/// Parses the [`TokenLutUnary`] struct from the given operator.
+///
+/// # Arguments
+/// * `operator` - The model operator as an [`Operator`]
+/// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `index` - The operator index
+/// * `code` - The builtin operator as a [`BuiltinOperator`]
+///
+pub(crate) fn parse(
+    operator: Operator,
+    tensors: Vector<ForwardsUOffset<Tensor>>,
+    index: usize,
+    code: BuiltinOperator,
+) -> Box<dyn ToTokens> {
+    let function = TokenUnaryFunction::new(code, operator);
+    let inputs = operator.inputs().unwrap();
+    let input_type = tensors.get(inputs.get(0) as usize).type_();
+    match input_type {
+        TensorType::INT8 => Box::new(TokenLutUnary::<i8>::new(operator, tensors, index, function)),
+        TensorType::UINT8 => Box::new(TokenLutUnary::<u8>::new(operator, tensors, index, function)),
+        TensorType::INT16 => Box::new(TokenLutUnary::<i16>::new(
+            operator, tensors, index, function,
+        )),
+        input_type => abort_call_site!(
+            "{:?} supports only INT8/UINT8/INT16 input tensors, got {:?}",
+            code,
+            input_type
+        ),
+    }
+}
+
+impl<T: TokenQuantized> TokenLutUnary<T> {
+    /// Builds the [`TokenLutUnary`] operator from the given model operator and tensors.
+    ///
+    /// # Arguments
+    /// * `operator` - The model operator as an [`Operator`]
+    /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `index` - The operator index
+    /// * `function` - The unary function as a [`TokenUnaryFunction`]
+    ///
+    pub(crate) fn new(
+        operator: Operator,
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        index: usize,
+        function: TokenUnaryFunction,
+    ) -> Self {
+        let inputs = operator.inputs().unwrap();
+        let input = TokenTensor::from_empty_tensor(tensors.get(inputs.get(0) as usize));
+        let output = TokenTensor::from_empty_tensor(
+            tensors.get(operator.outputs().unwrap().get(0) as usize),
+        );
+        let constants = Self::preprocess(&input, &output, function);
+        Self {
+            output,
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            constants,
+            index,
+        }
+    }
+
+    /// Pre-processes the operator, returning the lookup table of the results for every possible
+    /// quantized input.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor`]
+    /// * `output` - The output of the operator as a [`TokenTensor`]
+    /// * `function` - The unary function as a [`TokenUnaryFunction`]
+    ///
+    fn preprocess(
+        input: &TokenTensor<T>,
+        output: &TokenTensor<T>,
+        function: TokenUnaryFunction,
+    ) -> Vec<T> {
+        lookup_table(
+            (input.scale()[0], input.zero_point()[0]),
+            (output.scale()[0], output.zero_point()[0]),
+            |x| function.evaluate(x),
+        )
+    }
+}
+
+impl<T: TokenQuantized> ToTokens for TokenLutUnary<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let output_kind = self.output.kind_tokens();
+        let output_shape = self.output.shape();
+        let output_scale = self.output.scale();
+        let output_zero_point = self.output.zero_point();
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
+        let table_ident = format_ident!("table_{}", self.index);
+        let table = &self.constants;
+        let table_type = parse_str::<Type>(type_name::<T>()).unwrap();
+        let table_len = table.len();
+
+        let ts = quote! {
+            const #table_ident: [#table_type; #table_len] = [#(#table),*];
+            let #output: &mut microflow::tensor::#output_kind<_, #(#output_shape),*, 1usize> =
+                #output;
+            microflow::ops::lut_unary(
+                #input,
+                [#(#output_scale),*],
+                [#(#output_zero_point),*],
+                &#table_ident,
+                #output
+            );
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::TokenBuffer2D;
+    use crate::tensor::TokenTensor2D;
+
+    fn setup() -> TokenLutUnary<i8> {
+        TokenLutUnary {
+            output: TokenTensor::Tensor2D(TokenTensor2D {
+                buffer: TokenBuffer2D::new(),
+                shape: vec![2, 3],
+                scale: vec![0.003_906_25],
+                zero_point: vec![-128],
+            }),
+            input_index: 0,
+            output_index: 1,
+            constants: vec![-128; 256],
+            index: 2,
+        }
+    }
+
+    fn setup_input() -> TokenTensor<i8> {
+        TokenTensor::Tensor2D(TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![0.1],
+            zero_point: vec![0],
+        })
+    }
+
+    #[test]
+    fn unary_function_evaluate() {
+        assert_eq!(TokenUnaryFunction::Logistic.evaluate(0.), 0.5);
+        assert_eq!(TokenUnaryFunction::Tanh.evaluate(0.), 0.);
+        assert_eq!(TokenUnaryFunction::Exp.evaluate(0.), 1.);
+        assert_eq!(TokenUnaryFunction::Log.evaluate(1.), 0.);
+        assert_eq!(TokenUnaryFunction::Sqrt.evaluate(4.), 2.);
+        assert_eq!(TokenUnaryFunction::Rsqrt.evaluate(4.), 0.5);
+        assert_eq!(TokenUnaryFunction::Abs.evaluate(-2.), 2.);
+        assert_eq!(TokenUnaryFunction::Neg.evaluate(2.), -2.);
+        assert_eq!(TokenUnaryFunction::Floor.evaluate(-1.5), -2.);
+        assert_eq!(TokenUnaryFunction::Elu.evaluate(2.), 2.);
+        assert_eq!(TokenUnaryFunction::Elu.evaluate(-1.), -0.6321205588285577);
+        let gelu = TokenUnaryFunction::Gelu { approximate: false }.evaluate(1.);
+        let gelu_approximate = TokenUnaryFunction::Gelu { approximate: true }.evaluate(1.);
+        assert!((gelu - 0.841_344_746).abs() < 1e-9);
+        assert!((gelu - gelu_approximate).abs() < 1e-3);
+        assert_eq!(TokenUnaryFunction::HardSwish.evaluate(-4.), 0.);
+        assert_eq!(TokenUnaryFunction::HardSwish.evaluate(1.), 2. / 3.);
+        assert_eq!(TokenUnaryFunction::HardSwish.evaluate(4.), 4.);
+    }
+
+    #[test]
+    fn lut_unary_preprocess_logistic() {
+        let layer = setup();
+        let table =
+            TokenLutUnary::preprocess(&setup_input(), &layer.output, TokenUnaryFunction::Logistic);
+        assert_eq!(table.len(), 256);
+        assert_eq!(table[0], -128);
+        // The input 0. is mapped to 0.5
+        assert_eq!(table[128], 0);
+        assert_eq!(table[148], 97);
+        assert_eq!(table[255], 127);
+    }
+
+    #[test]
+    fn lut_unary_preprocess_tanh() {
+        let output = TokenTensor::Tensor2D(TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![0.007_812_5],
+            zero_point: vec![0],
+        });
+        let table = TokenLutUnary::preprocess(&setup_input(), &output, TokenUnaryFunction::Tanh);
+        assert_eq!(table[0], -128);
+        // The input 0. is mapped to 0.
+        assert_eq!(table[128], 0);
+        assert_eq!(table[148], 123);
+        assert_eq!(table[255], 127);
+    }
+
+    #[test]
+    fn lut_unary_preprocess_log() {
+        let layer = setup();
+        let table =
+            TokenLutUnary::preprocess(&setup_input(), &layer.output, TokenUnaryFunction::Log);
+        // The logarithm of the non-positive values saturates to the minimum
+        assert_eq!(table[0], -128);
+        assert_eq!(table[128], -128);
+        // The input 1. is mapped to 0.
+        assert_eq!(table[138], -128);
+        assert_eq!(table[255], 127);
+    }
+
+    #[test]
+    fn lut_unary_preprocess_i16() {
+        let tensor = TokenTensor::Tensor2D(TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![1. / 32768.],
+            zero_point: vec![0i16],
+        });
+        let table = TokenLutUnary::preprocess(&tensor, &tensor, TokenUnaryFunction::Abs);
+        assert_eq!(table.len(), 65536);
+        // The absolute value of the minimum saturates to the maximum
+        assert_eq!(table[0], i16::MAX);
+        assert_eq!(table[32767], 1);
+        assert_eq!(table[32768], 0);
+        assert_eq!(table[65535], i16::MAX);
+    }
+
+    #[test]
+    fn lut_unary_to_tokens() {
+        let layer = setup();
+        let table = vec![-128i8; 256];
+        assert_eq!(
+            layer.to_token_stream().to_string(),
+            quote! {
+                const table_2: [i8; 256usize] = [#(#table),*];
+                let tensor_1: &mut microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
+                    tensor_1;
+                microflow::ops::lut_unary(tensor_0, [0.00390625f32], [-128i8], &table_2, tensor_1);
+            }
+            .to_string()
+        )
+    }
+}
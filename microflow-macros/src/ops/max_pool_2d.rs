@@ -0,0 +1,155 @@
+use crate::activation::TokenFusedActivation;
+use crate::quantize::TokenQuantized;
+use crate::tensor::{tensor_ident, TokenTensor4D, TokenTensorViewPadding};
+use crate::tflite_flatbuffers::tflite::{Operator, Tensor, TensorType};
+use flatbuffers::{ForwardsUOffset, Vector};
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort_call_site;
+use quote::{quote, ToTokens};
+
+/// Represents the tokenized version of the `MaxPool2D` operator.
+pub(crate) struct TokenMaxPool2D<T: TokenQuantized> {
+    pub(crate) filter_shape: (usize, usize),
+    pub(crate) output: TokenTensor4D<T>,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) fused_activation: TokenFusedActivation<T>,
+    pub(crate) view_padding: TokenTensorViewPadding,
+    pub(crate) strides: (usize, usize),
+}
+
+/// Parses the [`TokenMaxPool2D`] struct from the given operator.
+///
+/// # Arguments
+/// * `operator` - The model operator as an [`Operator`]
+/// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+///
+pub(crate) fn parse(
+    operator: Operator,
+    tensors: Vector<ForwardsUOffset<Tensor>>,
+) -> Box<dyn ToTokens> {
+    let inputs = operator.inputs().unwrap();
+    let input_type = tensors.get(inputs.get(0) as usize).type_();
+    match input_type {
+        TensorType::INT8 => Box::new(TokenMaxPool2D::<i8>::new(operator, tensors)),
+        TensorType::UINT8 => Box::new(TokenMaxPool2D::<u8>::new(operator, tensors)),
+        input_type => abort_call_site!(
+            "MaxPool2D supports only INT8/UINT8 input tensors, got {:?}",
+            input_type
+        ),
+    }
+}
+
+impl<T: TokenQuantized> TokenMaxPool2D<T> {
+    /// Builds the [`TokenMaxPool2D`] operator from the given model operator and tensors.
+    ///
+    /// # Arguments
+    /// * `operator` - The model operator as an [`Operator`]
+    /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    ///
+    pub(crate) fn new(operator: Operator, tensors: Vector<ForwardsUOffset<Tensor>>) -> Self {
+        let inputs = operator.inputs().unwrap();
+        let output = TokenTensor4D::from_empty_tensor(
+            tensors.get(operator.outputs().unwrap().get(0) as usize),
+        );
+        let options = operator.builtin_options_as_pool_2_doptions().unwrap();
+        let fused_activation = TokenFusedActivation::new(
+            options.fused_activation_function(),
+            output.scale[0],
+            output.zero_point[0],
+        );
+        Self {
+            filter_shape: (
+                options.filter_height() as usize,
+                options.filter_width() as usize,
+            ),
+            output,
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            fused_activation,
+            view_padding: options.padding().into(),
+            strides: (options.stride_h() as usize, options.stride_w() as usize),
+        }
+    }
+}
+
+impl<T: TokenQuantized> ToTokens for TokenMaxPool2D<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let (filter_shape_0, filter_shape_1) = self.filter_shape;
+        let output_shape = &self.output.shape;
+        let output_scale = &self.output.scale;
+        let output_zero_point = &self.output.zero_point;
+        let fused_activation = &self.fused_activation;
+        let view_padding = self.view_padding;
+        let (strides_0, strides_1) = self.strides;
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
+
+        let ts = quote! {
+            let #output: &mut microflow::tensor::Tensor4D<_, #(#output_shape),*, 1usize> = #output;
+            microflow::ops::max_pool_2d(
+                #input,
+                (nalgebra::Const::<#filter_shape_0>, nalgebra::Const::<#filter_shape_1>),
+                [#(#output_scale),*],
+                [#(#output_zero_point),*],
+                microflow::ops::MaxPool2DOptions {
+                    fused_activation: #fused_activation,
+                    view_padding: #view_padding,
+                    strides: (#strides_0, #strides_1),
+                },
+                #output
+            );
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::TokenBuffer4D;
+
+    fn setup() -> TokenMaxPool2D<i8> {
+        TokenMaxPool2D {
+            filter_shape: (2, 3),
+            output: TokenTensor4D {
+                buffer: TokenBuffer4D::new(),
+                shape: vec![1, 2, 3, 2],
+                scale: vec![0.1],
+                zero_point: vec![2],
+            },
+            input_index: 0,
+            output_index: 1,
+            fused_activation: TokenFusedActivation::Relu,
+            view_padding: TokenTensorViewPadding::Valid,
+            strides: (2, 2),
+        }
+    }
+
+    #[test]
+    fn max_pool_2d_to_tokens() {
+        let layer = setup();
+        let fused_activation = &layer.fused_activation;
+        let view_padding = layer.view_padding;
+        assert_eq!(
+            layer.to_token_stream().to_string(),
+            quote! {
+                let tensor_1: &mut microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 2usize, 1usize> =
+                    tensor_1;
+                microflow::ops::max_pool_2d(
+                    tensor_0,
+                    (nalgebra::Const::<2usize>, nalgebra::Const::<3usize>),
+                    [0.1f32],
+                    [2i8],
+                    microflow::ops::MaxPool2DOptions {
+                        fused_activation: #fused_activation,
+                        view_padding: #view_padding,
+                        strides: (2usize, 2usize),
+                    },
+                    tensor_1
+                );
+            }
+            .to_string()
+        );
+    }
+}
@@ -1,6 +1,10 @@
+pub(crate) mod add;
 pub(crate) mod average_pool_2d;
 pub(crate) mod conv_2d;
 pub(crate) mod depthwise_conv_2d;
 pub(crate) mod fully_connected;
+pub(crate) mod lut_unary;
+pub(crate) mod max_pool_2d;
+pub(crate) mod quantize;
 pub(crate) mod reshape;
 pub(crate) mod softmax;
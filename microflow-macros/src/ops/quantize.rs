@@ -0,0 +1,246 @@
+use crate::quantize::{TokenQuantized, TokenQuantizedMultiplier, TokenRequantization};
+use crate::tensor::{tensor_ident, TokenTensor};
+use crate::tflite_flatbuffers::tflite::{Operator, Tensor, TensorType};
+use flatbuffers::{ForwardsUOffset, Vector};
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort_call_site;
+use quote::{quote, ToTokens};
+use simba::scalar::SupersetOf;
+
+/// Represents the tokenized version of the `Quantize` operator.
+pub(crate) struct TokenQuantize<U: TokenQuantized> {
+    pub(crate) output: TokenTensor<U>,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) constants: (f32, f32),
+    pub(crate) integer_constants: Option<(TokenQuantizedMultiplier, i32)>,
+}
+
+/// Parses the [`TokenQuantize`] struct from the given operator.
+///
+/// # Arguments
+/// * `operator` - The model operator as an [`Operator`]
+/// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
+///
+pub(crate) fn parse(
+    operator: Operator,
+    tensors: Vector<ForwardsUOffset<Tensor>>,
+    requantization: TokenRequantization,
+) -> Box<dyn ToTokens> {
+    let input_type = tensors
+        .get(operator.inputs().unwrap().get(0) as usize)
+        .type_();
+    match input_type {
+        TensorType::INT8 => parse_output::<i8>(operator, tensors, requantization),
+        TensorType::UINT8 => parse_output::<u8>(operator, tensors, requantization),
+        TensorType::INT16 => parse_output::<i16>(operator, tensors, requantization),
+        input_type => abort_call_site!(
+            "Quantize supports only INT8/UINT8/INT16 input tensors, got {:?}",
+            input_type
+        ),
+    }
+}
+
+/// Parses the [`TokenQuantize`] struct from the given operator, with an input of type `T`.
+///
+/// # Arguments
+/// * `operator` - The model operator as an [`Operator`]
+/// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
+///
+fn parse_output<T: TokenQuantized>(
+    operator: Operator,
+    tensors: Vector<ForwardsUOffset<Tensor>>,
+    requantization: TokenRequantization,
+) -> Box<dyn ToTokens> {
+    let output_type = tensors
+        .get(operator.outputs().unwrap().get(0) as usize)
+        .type_();
+    match output_type {
+        TensorType::INT8 => Box::new(TokenQuantize::<i8>::new::<T>(
+            operator,
+            tensors,
+            requantization,
+        )),
+        TensorType::UINT8 => Box::new(TokenQuantize::<u8>::new::<T>(
+            operator,
+            tensors,
+            requantization,
+        )),
+        TensorType::INT16 => Box::new(TokenQuantize::<i16>::new::<T>(
+            operator,
+            tensors,
+            requantization,
+        )),
+        output_type => abort_call_site!(
+            "Quantize supports only INT8/UINT8/INT16 output tensors, got {:?}",
+            output_type
+        ),
+    }
+}
+
+impl<U: TokenQuantized> TokenQuantize<U> {
+    /// Builds the [`TokenQuantize`] operator from the given model operator and tensors.
+    ///
+    /// # Arguments
+    /// * `operator` - The model operator as an [`Operator`]
+    /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `requantization` - The requantization mode as a [`TokenRequantization`]
+    ///
+    pub(crate) fn new<T: TokenQuantized>(
+        operator: Operator,
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        requantization: TokenRequantization,
+    ) -> Self {
+        let inputs = operator.inputs().unwrap();
+        let input = TokenTensor::<T>::from_empty_tensor(tensors.get(inputs.get(0) as usize));
+        let output = TokenTensor::from_empty_tensor(
+            tensors.get(operator.outputs().unwrap().get(0) as usize),
+        );
+        let constants = Self::preprocess(&input, &output);
+        let integer_constants = (requantization == TokenRequantization::Integer)
+            .then(|| Self::preprocess_integer(&input, &output));
+        Self {
+            output,
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            constants,
+            integer_constants,
+        }
+    }
+
+    /// Pre-processes the operator, returning the tuple of constants.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor`]
+    /// * `output` - The output of the operator as a [`TokenTensor`]
+    ///
+    fn preprocess<T: TokenQuantized>(
+        input: &TokenTensor<T>,
+        output: &TokenTensor<U>,
+    ) -> (f32, f32) {
+        let scale = input.scale()[0] / output.scale()[0];
+        (scale, -scale * f32::from_subset(&input.zero_point()[0]))
+    }
+
+    /// Pre-processes the operator for the integer-only requantization, returning the tuple of
+    /// constants.
+    /// Equivalent to the `Requantize` function of TensorFlow Lite.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor`]
+    /// * `output` - The output of the operator as a [`TokenTensor`]
+    ///
+    fn preprocess_integer<T: TokenQuantized>(
+        input: &TokenTensor<T>,
+        output: &TokenTensor<U>,
+    ) -> (TokenQuantizedMultiplier, i32) {
+        (
+            TokenQuantizedMultiplier::new(input.scale()[0] as f64 / output.scale()[0] as f64),
+            -i32::from_subset(&input.zero_point()[0]),
+        )
+    }
+}
+
+impl<U: TokenQuantized> ToTokens for TokenQuantize<U> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let output_kind = self.output.kind_tokens();
+        let output_shape = self.output.shape();
+        let output_scale = self.output.scale();
+        let output_zero_point = self.output.zero_point();
+        let constants = match self.integer_constants {
+            Some((constants_0, constants_1)) => quote!((#constants_0, #constants_1)),
+            None => {
+                let (constants_0, constants_1) = self.constants;
+                quote!((#constants_0, #constants_1))
+            }
+        };
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
+
+        let ts = quote! {
+            let #output: &mut microflow::tensor::#output_kind<_, #(#output_shape),*, 1usize> =
+                #output;
+            microflow::ops::quantize(
+                #input,
+                [#(#output_scale),*],
+                [#(#output_zero_point),*],
+                #constants,
+                #output
+            );
+        };
+        ts.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::TokenBuffer2D;
+    use crate::tensor::TokenTensor2D;
+
+    fn setup() -> TokenQuantize<u8> {
+        TokenQuantize {
+            output: TokenTensor::Tensor2D(TokenTensor2D {
+                buffer: TokenBuffer2D::new(),
+                shape: vec![2, 3],
+                scale: vec![0.5],
+                zero_point: vec![125],
+            }),
+            input_index: 0,
+            output_index: 1,
+            constants: (3., 4.),
+            integer_constants: None,
+        }
+    }
+
+    #[test]
+    fn quantize_preprocess() {
+        let layer = setup();
+        let input = TokenTensor::<i8>::Tensor2D(TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![0.25],
+            zero_point: vec![-6],
+        });
+        let constants = TokenQuantize::preprocess(&input, &layer.output);
+        assert_eq!(constants, (0.5, 3.));
+    }
+
+    #[test]
+    fn quantize_preprocess_integer() {
+        let layer = setup();
+        let input = TokenTensor::<i8>::Tensor2D(TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![0.25],
+            zero_point: vec![-6],
+        });
+        let constants = TokenQuantize::preprocess_integer(&input, &layer.output);
+        assert_eq!(
+            constants,
+            (
+                TokenQuantizedMultiplier {
+                    multiplier: 1073741824,
+                    shift: 0
+                },
+                6
+            )
+        );
+    }
+
+    #[test]
+    fn quantize_to_tokens() {
+        let layer = setup();
+        assert_eq!(
+            layer.to_token_stream().to_string(),
+            quote! {
+                let tensor_1: &mut microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
+                    tensor_1;
+                microflow::ops::quantize(tensor_0, [0.5f32], [125u8], (3f32, 4f32), tensor_1);
+            }
+            .to_string()
+        );
+    }
+}
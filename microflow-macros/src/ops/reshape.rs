@@ -1,3 +1,4 @@
+use crate::tensor::tensor_ident;
 use crate::tflite_flatbuffers::tflite::{Operator, Tensor};
 use flatbuffers::{ForwardsUOffset, Vector};
 use proc_macro2::TokenStream as TokenStream2;
@@ -7,6 +8,8 @@ use quote::{quote, ToTokens};
 /// Represents the tokenized version of the `Reshape` operator.
 pub(crate) struct TokenReshape {
     pub(crate) output_shape: Vec<usize>,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
 }
 
 /// Parses the [`TokenReshape`] struct from the given operator.
@@ -30,6 +33,7 @@ impl TokenReshape {
     /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
     ///
     pub(crate) fn new(operator: Operator, tensors: Vector<ForwardsUOffset<Tensor>>) -> Self {
+        let inputs = operator.inputs().unwrap();
         let output_shape: Vec<_> = tensors
             .get(operator.outputs().unwrap().get(0) as usize)
             .shape()
@@ -37,7 +41,11 @@ impl TokenReshape {
             .iter()
             .map(|e| e as usize)
             .collect();
-        Self { output_shape }
+        Self {
+            output_shape,
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+        }
     }
 }
 
@@ -53,10 +61,13 @@ impl ToTokens for TokenReshape {
                 output_shape
             ),
         };
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
 
         let ts = quote! {
-            let input: microflow::tensor::#output_tensor<_, #(#output_shape),*, 1usize> =
-                microflow::ops::reshape(input);
+            let #output: &mut microflow::tensor::#output_tensor<_, #(#output_shape),*, 1usize> =
+                #output;
+            microflow::ops::reshape(#input, #output);
         };
         ts.to_tokens(tokens)
     }
@@ -69,6 +80,8 @@ mod tests {
     fn setup() -> TokenReshape {
         TokenReshape {
             output_shape: vec![2, 3],
+            input_index: 0,
+            output_index: 1,
         }
     }
 
@@ -78,8 +91,9 @@ mod tests {
         assert_eq!(
             layer.to_token_stream().to_string(),
             quote! {
-                let input: microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
-                    microflow::ops::reshape(input);
+                let tensor_1: &mut microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
+                    tensor_1;
+                microflow::ops::reshape(tensor_0, tensor_1);
             }
             .to_string()
         )
@@ -1,14 +1,20 @@
-use crate::quantize::TokenQuantized;
-use crate::tensor::TokenTensor2D;
+use crate::quantize::{TokenQuantized, TokenQuantizedMultiplier, TokenRequantization};
+use crate::tensor::{tensor_ident, TokenTensor};
 use crate::tflite_flatbuffers::tflite::{Operator, Tensor, TensorType};
 use flatbuffers::{ForwardsUOffset, Vector};
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::abort_call_site;
-use quote::{quote, ToTokens};
+use quote::{format_ident, quote, ToTokens};
 
 /// Represents the tokenized version of the `Softmax` operator.
 pub(crate) struct TokenSoftmax<T: TokenQuantized> {
-    pub(crate) output: TokenTensor2D<T>,
+    pub(crate) output: TokenTensor<T>,
+    pub(crate) input_index: usize,
+    pub(crate) output_index: usize,
+    pub(crate) constants: f32,
+    pub(crate) integer_constants: Option<(TokenQuantizedMultiplier, i32)>,
+    pub(crate) table: Option<Vec<u32>>,
+    pub(crate) index: usize,
 }
 
 /// Parses the [`TokenSoftmax`] struct from the given operator.
@@ -16,16 +22,30 @@ pub(crate) struct TokenSoftmax<T: TokenQuantized> {
 /// # Arguments
 /// * `operator` - The model operator as an [`Operator`]
 /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `index` - The operator index
+/// * `requantization` - The requantization mode as a [`TokenRequantization`]
 ///
 pub(crate) fn parse(
     operator: Operator,
     tensors: Vector<ForwardsUOffset<Tensor>>,
+    index: usize,
+    requantization: TokenRequantization,
 ) -> Box<dyn ToTokens> {
     let inputs = operator.inputs().unwrap();
     let input_type = tensors.get(inputs.get(0) as usize).type_();
     match input_type {
-        TensorType::INT8 => Box::new(TokenSoftmax::<i8>::new(operator, tensors)),
-        TensorType::UINT8 => Box::new(TokenSoftmax::<u8>::new(operator, tensors)),
+        TensorType::INT8 => Box::new(TokenSoftmax::<i8>::new(
+            operator,
+            tensors,
+            index,
+            requantization,
+        )),
+        TensorType::UINT8 => Box::new(TokenSoftmax::<u8>::new(
+            operator,
+            tensors,
+            index,
+            requantization,
+        )),
         input_type => abort_call_site!(
             "Softmax supports only INT8/UINT8 input tensors, got {:?}",
             input_type
@@ -39,24 +59,141 @@ impl<T: TokenQuantized> TokenSoftmax<T> {
     /// # Arguments
     /// * `operator` - The model operator as an [`Operator`]
     /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `index` - The operator index
+    /// * `requantization` - The requantization mode as a [`TokenRequantization`]
     ///
-    pub(crate) fn new(operator: Operator, tensors: Vector<ForwardsUOffset<Tensor>>) -> Self {
-        let output = TokenTensor2D::from_empty_tensor(
+    pub(crate) fn new(
+        operator: Operator,
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        index: usize,
+        requantization: TokenRequantization,
+    ) -> Self {
+        let inputs = operator.inputs().unwrap();
+        let input = TokenTensor::from_empty_tensor(tensors.get(inputs.get(0) as usize));
+        let output = TokenTensor::from_empty_tensor(
             tensors.get(operator.outputs().unwrap().get(0) as usize),
         );
-        Self { output }
+        let options = operator.builtin_options_as_softmax_options().unwrap();
+        let constants = Self::preprocess(&input, options.beta());
+        let integer_constants = (requantization == TokenRequantization::Integer)
+            .then(|| Self::preprocess_integer(&input, options.beta()));
+        // The lookup table replaces the floating-point exponentials when the output has the usual
+        // scale of 1 / 256, as the normalization is then exact in integer arithmetic
+        let table = (requantization == TokenRequantization::Float
+            && output.scale()[0] == 1. / 256.)
+            .then(|| Self::preprocess_table(&input, options.beta()));
+        Self {
+            output,
+            input_index: inputs.get(0) as usize,
+            output_index: operator.outputs().unwrap().get(0) as usize,
+            constants,
+            integer_constants,
+            table,
+            index,
+        }
+    }
+
+    /// Pre-processes the operator, returning the input scale multiplied by the inverse
+    /// temperature.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor`]
+    /// * `beta` - The inverse temperature of the operator
+    ///
+    fn preprocess(input: &TokenTensor<T>, beta: f32) -> f32 {
+        beta * input.scale()[0]
+    }
+
+    /// Pre-processes the operator for the lookup table of the exponentials, returning the
+    /// exponentials of the 256 possible differences from the maximum input, in Q16 format.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor`]
+    /// * `beta` - The inverse temperature of the operator
+    ///
+    fn preprocess_table(input: &TokenTensor<T>, beta: f32) -> Vec<u32> {
+        let scale = beta as f64 * input.scale()[0] as f64;
+        (0..256)
+            .map(|d| ((-scale * d as f64).exp() * (1 << 16) as f64).round() as u32)
+            .collect()
+    }
+
+    /// Pre-processes the operator for the integer-only arithmetic, returning the tuple of
+    /// constants.
+    /// Equivalent to the `PreprocessSoftmaxScaling` and `CalculateInputRadius` functions of
+    /// TensorFlow Lite.
+    ///
+    /// # Arguments
+    /// * `input` - The input of the operator as a [`TokenTensor`]
+    /// * `beta` - The inverse temperature of the operator
+    ///
+    fn preprocess_integer(input: &TokenTensor<T>, beta: f32) -> (TokenQuantizedMultiplier, i32) {
+        // The differences from the maximum are rescaled to Q5.26 format
+        const SCALED_DIFF_INTEGER_BITS: i32 = 5;
+        let multiplier = TokenQuantizedMultiplier::new(
+            (beta as f64
+                * input.scale()[0] as f64
+                * (1i64 << (31 - SCALED_DIFF_INTEGER_BITS)) as f64)
+                .min(i32::MAX as f64),
+        );
+        // The differences below the minimum representable one are discarded
+        let diff_min = ((1 << SCALED_DIFF_INTEGER_BITS) - 1) as f64
+            * (1i64 << (31 - SCALED_DIFF_INTEGER_BITS)) as f64
+            / 2f64.powi(multiplier.shift);
+        (multiplier, -diff_min.floor() as i32)
     }
 }
 
 impl<T: TokenQuantized> ToTokens for TokenSoftmax<T> {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let output_shape = &self.output.shape;
-        let output_scale = &self.output.scale;
-        let output_zero_point = &self.output.zero_point;
+        let output_kind = self.output.kind_tokens();
+        let output_shape = self.output.shape();
+        let output_scale = self.output.scale();
+        let output_zero_point = self.output.zero_point();
+        let input = tensor_ident(self.input_index);
+        let output = tensor_ident(self.output_index);
 
-        let ts = quote! {
-            let input: microflow::tensor::Tensor2D<_, #(#output_shape),*, 1usize> =
-                microflow::ops::softmax(input, [#(#output_scale),*], [#(#output_zero_point),*]);
+        let ts = match (self.integer_constants, &self.table) {
+            (Some((constants_0, constants_1)), _) => quote! {
+                let #output: &mut microflow::tensor::#output_kind<_, #(#output_shape),*, 1usize> =
+                    #output;
+                microflow::ops::softmax_integer(
+                    #input,
+                    [#(#output_scale),*],
+                    [#(#output_zero_point),*],
+                    (#constants_0, #constants_1),
+                    #output
+                );
+            },
+            (None, Some(table)) => {
+                let table_ident = format_ident!("table_{}", self.index);
+                quote! {
+                    const #table_ident: [u32; 256usize] = [#(#table),*];
+                    let #output: &mut microflow::tensor::#output_kind<_, #(#output_shape),*, 1usize> =
+                        #output;
+                    microflow::ops::softmax_lut(
+                        #input,
+                        [#(#output_scale),*],
+                        [#(#output_zero_point),*],
+                        &#table_ident,
+                        #output
+                    );
+                }
+            }
+            (None, None) => {
+                let constants = self.constants;
+                quote! {
+                    let #output: &mut microflow::tensor::#output_kind<_, #(#output_shape),*, 1usize> =
+                        #output;
+                    microflow::ops::softmax(
+                        #input,
+                        [#(#output_scale),*],
+                        [#(#output_zero_point),*],
+                        #constants,
+                        #output
+                    );
+                }
+            }
         };
         ts.to_tokens(tokens);
     }
@@ -65,27 +202,135 @@ impl<T: TokenQuantized> ToTokens for TokenSoftmax<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::buffer::TokenBuffer2D;
+    use crate::buffer::{TokenBuffer2D, TokenBuffer4D};
+    use crate::tensor::{TokenTensor2D, TokenTensor4D};
 
     fn setup() -> TokenSoftmax<i8> {
         TokenSoftmax {
-            output: TokenTensor2D {
+            output: TokenTensor::Tensor2D(TokenTensor2D {
                 buffer: TokenBuffer2D::new(),
                 shape: vec![2, 3],
                 scale: vec![0.3],
                 zero_point: vec![4],
-            },
+            }),
+            input_index: 0,
+            output_index: 1,
+            constants: 5.,
+            integer_constants: None,
+            table: None,
+            index: 2,
         }
     }
 
+    fn setup_input() -> TokenTensor<i8> {
+        TokenTensor::Tensor2D(TokenTensor2D {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![0.7],
+            zero_point: vec![8],
+        })
+    }
+
+    #[test]
+    fn softmax_preprocess() {
+        let constants = TokenSoftmax::preprocess(&setup_input(), 2.);
+        assert_eq!(constants, 1.4);
+    }
+
+    #[test]
+    fn softmax_preprocess_table() {
+        let table = TokenSoftmax::preprocess_table(&setup_input(), 1.);
+        assert_eq!(table.len(), 256);
+        assert_eq!(&table[..4], &[65536, 32544, 16161, 8025]);
+        assert_eq!(table[255], 0);
+    }
+
+    #[test]
+    fn softmax_preprocess_integer() {
+        let constants = TokenSoftmax::preprocess_integer(&setup_input(), 1.);
+        assert_eq!(
+            constants,
+            (
+                TokenQuantizedMultiplier {
+                    multiplier: 1503238528,
+                    shift: 26
+                },
+                -31
+            )
+        );
+    }
+
+    #[test]
+    fn softmax_preprocess_integer_negative_shift() {
+        let input = TokenTensor::Tensor2D(TokenTensor2D::<i8> {
+            buffer: TokenBuffer2D::new(),
+            shape: vec![2, 3],
+            scale: vec![1e-9],
+            zero_point: vec![8],
+        });
+        let constants = TokenSoftmax::preprocess_integer(&input, 1.);
+        assert_eq!(
+            constants,
+            (
+                TokenQuantizedMultiplier {
+                    multiplier: 1152921472,
+                    shift: -3
+                },
+                i32::MIN
+            )
+        );
+    }
+
     #[test]
     fn softmax_to_tokens() {
         let layer = setup();
         assert_eq!(
             layer.to_token_stream().to_string(),
             quote! {
-                let input: microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
-                    microflow::ops::softmax(input, [0.3f32], [4i8]);
+                let tensor_1: &mut microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
+                    tensor_1;
+                microflow::ops::softmax(tensor_0, [0.3f32], [4i8], 5f32, tensor_1);
+            }
+            .to_string()
+        )
+    }
+
+    #[test]
+    fn softmax_to_tokens_4d() {
+        let layer = TokenSoftmax {
+            output: TokenTensor::Tensor4D(TokenTensor4D {
+                buffer: TokenBuffer4D::new(),
+                shape: vec![1, 2, 3, 4],
+                scale: vec![0.3],
+                zero_point: vec![4],
+            }),
+            ..setup()
+        };
+        assert_eq!(
+            layer.to_token_stream().to_string(),
+            quote! {
+                let tensor_1: &mut microflow::tensor::Tensor4D<_, 1usize, 2usize, 3usize, 4usize, 1usize> =
+                    tensor_1;
+                microflow::ops::softmax(tensor_0, [0.3f32], [4i8], 5f32, tensor_1);
+            }
+            .to_string()
+        )
+    }
+
+    #[test]
+    fn softmax_to_tokens_table() {
+        let layer = TokenSoftmax {
+            table: Some(vec![65536; 256]),
+            ..setup()
+        };
+        let table = vec![65536u32; 256];
+        assert_eq!(
+            layer.to_token_stream().to_string(),
+            quote! {
+                const table_2: [u32; 256usize] = [#(#table),*];
+                let tensor_1: &mut microflow::tensor::Tensor2D<_, 2usize, 3usize, 1usize> =
+                    tensor_1;
+                microflow::ops::softmax_lut(tensor_0, [0.3f32], [4i8], &table_2, tensor_1);
             }
             .to_string()
         )
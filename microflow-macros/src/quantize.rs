@@ -1,7 +1,9 @@
 use byterepr::ByteReprNum;
 use nalgebra::Scalar;
-use quote::ToTokens;
-use simba::scalar::SubsetOf;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use simba::scalar::{SubsetOf, SupersetOf};
+use std::mem::size_of;
 
 /// Represents the trait to constrain a type to be quantized and tokenized.
 pub(crate) trait TokenQuantized:
@@ -13,3 +15,183 @@ impl<T: Scalar + ByteReprNum + ToTokens + SubsetOf<i32> + SubsetOf<f32> + Subset
     TokenQuantized for T
 {
 }
+
+/// Represents the requantization mode of the operators.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum TokenRequantization {
+    /// The accumulators are requantized with floating-point arithmetic.
+    Float,
+    /// The accumulators are requantized with integer-only (fixed-point) arithmetic.
+    Integer,
+}
+
+/// Represents the tokenized version of the `QuantizedMultiplier`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct TokenQuantizedMultiplier {
+    pub(crate) multiplier: i32,
+    pub(crate) shift: i32,
+}
+
+impl TokenQuantizedMultiplier {
+    /// Builds the [`TokenQuantizedMultiplier`] approximating the given real multiplier.
+    /// Equivalent to the `QuantizeMultiplier` function of TensorFlow Lite.
+    ///
+    /// # Arguments
+    /// * `real` - The real multiplier
+    ///
+    pub(crate) fn new(real: f64) -> Self {
+        if real == 0. {
+            return Self {
+                multiplier: 0,
+                shift: 0,
+            };
+        }
+        // Decompose the multiplier as `q * 2^shift`, with `q` in [0.5, 1)
+        let mut q = real.abs();
+        let mut shift = 0;
+        while q >= 1. {
+            q /= 2.;
+            shift += 1;
+        }
+        while q < 0.5 {
+            q *= 2.;
+            shift -= 1;
+        }
+        let mut q_fixed = (q * (1i64 << 31) as f64).round() as i64;
+        if q_fixed == 1 << 31 {
+            q_fixed /= 2;
+            shift += 1;
+        }
+        if shift < -31 {
+            q_fixed = 0;
+            shift = 0;
+        }
+        Self {
+            multiplier: (q_fixed as i32) * real.signum() as i32,
+            shift,
+        }
+    }
+}
+
+/// Evaluates the given function on every quantized value of `T`, from the minimum to the maximum.
+/// Returns the lookup table of the results, quantized and saturated to the bounds of `T`.
+///
+/// # Arguments
+/// * `input` - The scale and the zero point of the input quantization
+/// * `output` - The scale and the zero point of the output quantization
+/// * `f` - The function to evaluate on the dequantized values
+///
+pub(crate) fn lookup_table<T: TokenQuantized>(
+    input: (f32, T),
+    output: (f32, T),
+    f: impl Fn(f64) -> f64,
+) -> Vec<T> {
+    let (min, max) = bounds::<T>();
+    let input_zero_point = i64::from_subset(&input.1) as f64;
+    let output_zero_point = i64::from_subset(&output.1) as f64;
+    (min..=max)
+        .map(|v| {
+            let y = f(input.0 as f64 * (v as f64 - input_zero_point));
+            let y = (y / output.0 as f64).round() + output_zero_point;
+            // The undefined results (i.e., outside of the function domain) saturate to the minimum
+            let y = if y.is_nan() {
+                min as f64
+            } else {
+                y.clamp(min as f64, max as f64)
+            };
+            i64::to_subset_unchecked(&(y as i64))
+        })
+        .collect()
+}
+
+impl ToTokens for TokenQuantizedMultiplier {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let multiplier = self.multiplier;
+        let shift = self.shift;
+        let ts = quote!(microflow::quantize::QuantizedMultiplier::new(#multiplier, #shift));
+        ts.to_tokens(tokens);
+    }
+}
+
+/// Returns the minimum and the maximum values of `T`.
+fn bounds<T: TokenQuantized>() -> (i64, i64) {
+    let len = 1i64 << (8 * size_of::<T>());
+    // The type is signed if the cast of -1 remains negative
+    let minus_one: T = (-1i64).to_subset_unchecked();
+    let signed = i64::from_subset(&minus_one) < 0;
+    let min = if signed { -len / 2 } else { 0 };
+    (min, min + len - 1)
+}
+
+/// Quantizes the given value, saturating it to the bounds of `T`.
+/// The value is divided by the scale in single precision, as TensorFlow Lite does for the bounds of
+/// the fused activation functions.
+///
+/// # Arguments
+/// * `x` - The value to quantize
+/// * `scale` - The quantization scale
+/// * `zero_point` - The quantization zero point
+///
+pub(crate) fn quantize<T: TokenQuantized>(x: f32, scale: f32, zero_point: T) -> T {
+    let (min, max) = bounds::<T>();
+    let y = (x / scale).round() as i64 + i64::from_subset(&zero_point);
+    i64::to_subset_unchecked(&y.clamp(min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantized_multiplier_new() {
+        let multiplier = TokenQuantizedMultiplier::new(0.3);
+        assert_eq!(multiplier.multiplier, 1288490189);
+        assert_eq!(multiplier.shift, -1);
+        let multiplier = TokenQuantizedMultiplier::new(1.5);
+        assert_eq!(multiplier.multiplier, 1610612736);
+        assert_eq!(multiplier.shift, 1);
+        assert_eq!(TokenQuantizedMultiplier::new(0.).multiplier, 0);
+    }
+
+    #[test]
+    fn quantized_multiplier_to_tokens() {
+        let multiplier = TokenQuantizedMultiplier {
+            multiplier: 1,
+            shift: 2,
+        };
+        assert_eq!(
+            multiplier.to_token_stream().to_string(),
+            quote!(microflow::quantize::QuantizedMultiplier::new(1i32, 2i32)).to_string()
+        );
+    }
+
+    #[test]
+    fn quantize_saturated() {
+        assert_eq!(quantize::<i8>(6., 0.1, 2), 62);
+        assert_eq!(quantize::<i8>(-1., 0.1, 2), -8);
+        assert_eq!(quantize::<i8>(6., 0.01, 2), 127);
+        assert_eq!(quantize::<u8>(-1., 0.1, 2), 0);
+    }
+
+    #[test]
+    fn lookup_table_signed() {
+        let table = lookup_table::<i8>((0.5, -1), (0.25, 3), |x| x);
+        assert_eq!(table.len(), 256);
+        assert_eq!(table[0], -128);
+        // The value -1 (i.e., 0.) is mapped to the output zero point
+        assert_eq!(table[127], 3);
+        assert_eq!(table[128], 5);
+        assert_eq!(table[255], 127);
+    }
+
+    #[test]
+    fn lookup_table_unsigned() {
+        let table = lookup_table::<u8>((0.5, 10), (1., 0), f64::sqrt);
+        assert_eq!(table.len(), 256);
+        // The square root of negative values is undefined, saturated to the minimum
+        assert_eq!(table[0], 0);
+        assert_eq!(table[10], 0);
+        assert_eq!(table[18], 2);
+        assert_eq!(table[255], 11);
+    }
+}
@@ -4,13 +4,13 @@ use std::mem::size_of;
 use flatbuffers::{ForwardsUOffset, Vector};
 use nalgebra::DMatrix;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::{quote, ToTokens};
+use quote::{format_ident, quote, ToTokens};
 use simba::scalar::SupersetOf;
-use syn::{parse_str, Type};
+use syn::{parse_str, Ident, Type};
 
-use crate::buffer::{TokenBuffer2D, TokenBuffer4D};
+use crate::buffer::{TokenBuffer2D, TokenBuffer4D, TokenBufferEmission};
 use crate::quantize::TokenQuantized;
-use crate::tflite_flatbuffers::tflite::{Buffer, Padding, Tensor};
+use crate::tflite_flatbuffers::tflite::{Buffer, Operator, Padding, Tensor};
 
 /// Represents the tokenized version of the `TensorViewPadding`.
 #[derive(Copy, Clone)]
@@ -37,6 +37,13 @@ pub(crate) struct TokenTensor4D<T: TokenQuantized> {
     pub(crate) zero_point: Vec<T>,
 }
 
+/// Represents the tokenized version of a tensor, i.e., either a `Tensor2D` or a `Tensor4D`.
+#[derive(Debug)]
+pub(crate) enum TokenTensor<T: TokenQuantized> {
+    Tensor2D(TokenTensor2D<T>),
+    Tensor4D(TokenTensor4D<T>),
+}
+
 impl ToTokens for TokenTensorViewPadding {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
         match self {
@@ -122,20 +129,64 @@ impl<T: TokenQuantized> TokenTensor2D<T> {
     }
 }
 
-impl<T: TokenQuantized> ToTokens for TokenTensor2D<T> {
-    fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let buffer = &self.buffer;
+impl<T: TokenQuantized> TokenTensor2D<T> {
+    /// Returns the tokens of the tensor, emitting its buffer in the given mode.
+    ///
+    /// # Arguments
+    /// * `emission` - The emission mode of the buffer as a [`TokenBufferEmission`]
+    ///
+    pub fn emit(&self, emission: TokenBufferEmission) -> TokenStream2 {
+        let buffer = match emission {
+            TokenBufferEmission::Matrix => self.buffer.to_token_stream(),
+            TokenBufferEmission::Raw => self.buffer.raw_tokens(),
+        };
         let scale = &self.scale;
         let zero_point = &self.zero_point;
 
-        let ts = quote! {
+        quote! {
             microflow::tensor::Tensor2D::new(
                 #buffer,
                 [#(#scale),*],
                 [#(#zero_point),*]
             )
-        };
-        ts.to_tokens(tokens);
+        }
+    }
+}
+
+impl TokenTensor2D<i32> {
+    /// Builds the [`TokenTensor2D`] of the biases of an operator, i.e., of its third input.
+    /// Missing biases (either omitted or with index -1) are replaced by zeros, so that the bias
+    /// term is omitted from the constants of the operator.
+    ///
+    /// # Arguments
+    /// * `operator` - The model operator as an [`Operator`]
+    /// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+    /// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
+    /// * `channels` - The number of output channels of the operator
+    ///
+    pub fn from_bias(
+        operator: Operator,
+        tensors: Vector<ForwardsUOffset<Tensor>>,
+        buffers: Vector<ForwardsUOffset<Buffer>>,
+        channels: usize,
+    ) -> Self {
+        match operator.inputs().unwrap().iter().nth(2) {
+            Some(index) if index >= 0 => {
+                Self::from_buffered_tensor(tensors.get(index as usize), buffers)
+            }
+            _ => Self {
+                buffer: TokenBuffer2D::from(DMatrix::zeros(channels, 1)),
+                shape: vec![channels, 1],
+                scale: vec![0.],
+                zero_point: vec![0],
+            },
+        }
+    }
+}
+
+impl<T: TokenQuantized> ToTokens for TokenTensor2D<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        self.emit(TokenBufferEmission::Matrix).to_tokens(tokens);
     }
 }
 
@@ -146,9 +197,13 @@ impl<T: TokenQuantized> TokenTensor4D<T> {
     /// * `tensor` - The empty model tensor as a [`Tensor`]
     ///
     pub fn from_empty_tensor(tensor: Tensor) -> Self {
+        let mut shape: Vec<_> = tensor.shape().unwrap().iter().map(|e| e as usize).collect();
+        while shape.len() < 4 {
+            shape.insert(0, 1);
+        }
         Self {
             buffer: TokenBuffer4D::new(),
-            shape: tensor.shape().unwrap().iter().map(|e| e as usize).collect(),
+            shape,
             scale: tensor
                 .quantization()
                 .unwrap()
@@ -208,23 +263,144 @@ impl<T: TokenQuantized> TokenTensor4D<T> {
     }
 }
 
-impl<T: TokenQuantized> ToTokens for TokenTensor4D<T> {
-    fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let buffer = &self.buffer;
+impl<T: TokenQuantized> TokenTensor4D<T> {
+    /// Returns the tokens of the tensor, emitting its buffer in the given mode.
+    ///
+    /// # Arguments
+    /// * `emission` - The emission mode of the buffer as a [`TokenBufferEmission`]
+    ///
+    pub fn emit(&self, emission: TokenBufferEmission) -> TokenStream2 {
+        let buffer = match emission {
+            TokenBufferEmission::Matrix => self.buffer.to_token_stream(),
+            TokenBufferEmission::Raw => self.buffer.raw_tokens(),
+        };
         let scale = &self.scale;
         let zero_point = &self.zero_point;
 
-        let ts = quote! {
+        quote! {
             microflow::tensor::Tensor4D::new(
                 #buffer,
                 [#(#scale),*],
                 [#(#zero_point),*]
             )
-        };
-        ts.to_tokens(tokens);
+        }
     }
 }
 
+impl<T: TokenQuantized> ToTokens for TokenTensor4D<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        self.emit(TokenBufferEmission::Matrix).to_tokens(tokens);
+    }
+}
+
+impl<T: TokenQuantized> TokenTensor<T> {
+    /// Builds a [`TokenTensor`] from an empty [`Tensor`].
+    /// Tensors with rank up to 2 are mapped to a [`TokenTensor2D`], the others to a
+    /// [`TokenTensor4D`].
+    ///
+    /// # Arguments
+    /// * `tensor` - The empty model tensor as a [`Tensor`]
+    ///
+    pub fn from_empty_tensor(tensor: Tensor) -> Self {
+        if tensor.shape().unwrap().len() <= 2 {
+            Self::Tensor2D(TokenTensor2D::from_empty_tensor(tensor))
+        } else {
+            Self::Tensor4D(TokenTensor4D::from_empty_tensor(tensor))
+        }
+    }
+
+    /// Builds a [`TokenTensor`] of the given rank from a [`Tensor`] with a buffer.
+    /// Missing leading dimensions are set to 1, following the broadcasting rules.
+    ///
+    /// # Arguments
+    /// * `tensor` - The model tensor as a [`Tensor`]
+    /// * `buffer` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
+    /// * `rank` - The rank of the resulting tensor, either 2 or 4
+    ///
+    pub fn from_buffered_tensor(
+        tensor: Tensor,
+        buffers: Vector<ForwardsUOffset<Buffer>>,
+        rank: usize,
+    ) -> Self {
+        if rank == 2 {
+            let mut t = TokenTensor2D::from_empty_tensor(tensor);
+            let matrix = DMatrix::from_row_iterator(
+                t.shape[0],
+                t.shape[1],
+                buffers
+                    .get(tensor.buffer() as usize)
+                    .data()
+                    .unwrap()
+                    .bytes()
+                    .chunks_exact(size_of::<T>())
+                    .map(|e| T::from_le_bytes(e)),
+            );
+            t.buffer = TokenBuffer2D::from(matrix);
+            Self::Tensor2D(t)
+        } else {
+            Self::Tensor4D(TokenTensor4D::from_buffered_tensor(tensor, buffers))
+        }
+    }
+
+    /// Returns the shape of the tensor.
+    pub fn shape(&self) -> &Vec<usize> {
+        match self {
+            Self::Tensor2D(t) => &t.shape,
+            Self::Tensor4D(t) => &t.shape,
+        }
+    }
+
+    /// Returns the scale of the tensor.
+    pub fn scale(&self) -> &Vec<f32> {
+        match self {
+            Self::Tensor2D(t) => &t.scale,
+            Self::Tensor4D(t) => &t.scale,
+        }
+    }
+
+    /// Returns the zero point of the tensor.
+    pub fn zero_point(&self) -> &Vec<T> {
+        match self {
+            Self::Tensor2D(t) => &t.zero_point,
+            Self::Tensor4D(t) => &t.zero_point,
+        }
+    }
+
+    /// Returns the tokens of the tensor kind, i.e., either `Tensor2D` or `Tensor4D`.
+    pub fn kind_tokens(&self) -> TokenStream2 {
+        match self {
+            Self::Tensor2D(_) => quote!(Tensor2D),
+            Self::Tensor4D(_) => quote!(Tensor4D),
+        }
+    }
+
+    /// Returns the tokens of the [`Self`] type.
+    pub fn type_tokens(&self) -> TokenStream2 {
+        match self {
+            Self::Tensor2D(t) => t.type_tokens(),
+            Self::Tensor4D(t) => t.type_tokens(),
+        }
+    }
+}
+
+impl<T: TokenQuantized> ToTokens for TokenTensor<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        match self {
+            Self::Tensor2D(t) => t.to_tokens(tokens),
+            Self::Tensor4D(t) => t.to_tokens(tokens),
+        }
+    }
+}
+
+/// Returns the identifier of the binding holding the tensor with the given index.
+///
+/// # Arguments
+/// * `index` - The index of the tensor in the model
+///
+pub(crate) fn tensor_ident(index: usize) -> Ident {
+    format_ident!("tensor_{}", index)
+}
+
 #[cfg(test)]
 mod tests {
     use nalgebra::dmatrix;
@@ -261,6 +437,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tensor_from_2d_kind_tokens() {
+        let tensor = TokenTensor::Tensor2D(setup_2d());
+        assert_eq!(
+            tensor.kind_tokens().to_string(),
+            quote!(Tensor2D).to_string()
+        );
+        assert_eq!(tensor.shape(), &vec![2, 3]);
+    }
+
+    #[test]
+    fn tensor_from_4d_type_tokens() {
+        let tensor = TokenTensor::Tensor4D(setup_4d());
+        assert_eq!(
+            tensor.type_tokens().to_string(),
+            quote!(microflow::tensor::Tensor4D<i8, 2usize, 2usize, 3usize, 2usize, 2usize>)
+                .to_string(),
+        );
+    }
+
     #[test]
     fn view_padding_to_tokens() {
         let padding = TokenTensorViewPadding::from(Padding::VALID);
@@ -296,6 +492,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tensor_2d_emit_raw() {
+        let tensor = setup_2d();
+        let buffer = tensor.buffer.raw_tokens();
+        assert_eq!(
+            tensor.emit(TokenBufferEmission::Raw).to_string(),
+            quote! {
+                microflow::tensor::Tensor2D::new(
+                    #buffer,
+                    [0.7f32],
+                    [8i8]
+                )
+            }
+            .to_string()
+        );
+    }
+
     #[test]
     fn tensor_4d_type_tokens() {
         let tensor = setup_4d();
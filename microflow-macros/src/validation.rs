@@ -0,0 +1,673 @@
+use flatbuffers::{ForwardsUOffset, Vector};
+
+use crate::tflite_flatbuffers::tflite::{
+    root_as_model, Buffer, BuiltinOperator, BuiltinOptions, Model, Operator, OperatorCode, Tensor,
+    TensorType,
+};
+
+/// Represents the kind of a tensor used by the model.
+#[derive(Copy, Clone, PartialEq)]
+enum TensorKind {
+    /// The tensor is quantized and (usually) computed at inference time.
+    Variable,
+    /// The tensor contains the quantized weights, hence it must be stored in the model.
+    Weights,
+    /// The tensor contains the 32-bit biases, hence it must be stored in the model.
+    /// The biases are optional, i.e., the tensor can be omitted or have index -1.
+    Bias,
+    /// The tensor is quantized, also on 16 bits, and used by an elementwise operator (i.e., a
+    /// lookup table or a requantization).
+    Elementwise,
+    /// The tensor is either quantized or float, at the boundaries of the model.
+    Boundary,
+}
+
+impl TensorKind {
+    /// Returns the tensor types allowed for the kind.
+    fn types(&self) -> &'static [TensorType] {
+        match self {
+            Self::Variable | Self::Weights => &[TensorType::INT8, TensorType::UINT8],
+            Self::Bias => &[TensorType::INT32],
+            Self::Elementwise => &[TensorType::INT8, TensorType::UINT8, TensorType::INT16],
+            Self::Boundary => &[
+                TensorType::INT8,
+                TensorType::UINT8,
+                TensorType::INT16,
+                TensorType::FLOAT32,
+            ],
+        }
+    }
+
+    /// Returns whether the tensor must be stored in the model.
+    fn is_constant(&self) -> bool {
+        matches!(self, Self::Weights | Self::Bias)
+    }
+
+    /// Returns whether the tensor can be omitted.
+    fn is_optional(&self) -> bool {
+        matches!(self, Self::Bias)
+    }
+}
+
+/// Loads the model from the given bytes, validating every property read by the compiler.
+/// Returns the model, or a message describing the first invalid property found.
+///
+/// # Arguments
+/// * `buf` - The bytes of the model, in the TensorFlow Lite format
+///
+pub(crate) fn load(buf: &[u8]) -> Result<Model<'_>, String> {
+    let model = root_as_model(buf).map_err(|e| format!("invalid flatbuffer: {}", e))?;
+    validate(model)?;
+    Ok(model)
+}
+
+/// Returns the builtin operator of the given operator.
+/// As in TensorFlow Lite, the operator code is the maximum of the deprecated code, which is
+/// capped at 127, and of the newer one, which is not set by the older converters.
+///
+/// # Arguments
+/// * `operator_codes` - The model operator codes as a [`Vector<ForwardsUOffset<OperatorCode>>`]
+/// * `operator` - The model operator as an [`Operator`]
+///
+pub(crate) fn builtin_code(
+    operator_codes: Vector<ForwardsUOffset<OperatorCode>>,
+    operator: Operator,
+) -> BuiltinOperator {
+    let operator_code = operator_codes.get(operator.opcode_index() as usize);
+    BuiltinOperator(
+        (operator_code.deprecated_builtin_code() as i32).max(operator_code.builtin_code().0),
+    )
+}
+
+/// Validates the given model.
+///
+/// # Arguments
+/// * `model` - The model as a [`Model`]
+///
+fn validate(model: Model) -> Result<(), String> {
+    let subgraph = model
+        .subgraphs()
+        .filter(|subgraphs| !subgraphs.is_empty())
+        .ok_or("the model has no subgraphs")?
+        .get(0);
+    let tensors = subgraph.tensors().ok_or("the model has no tensors")?;
+    let buffers = model.buffers().ok_or("the model has no buffers")?;
+    let operator_codes = model
+        .operator_codes()
+        .ok_or("the model has no operator codes")?;
+    let operators = subgraph.operators().ok_or("the model has no operators")?;
+
+    for (kind, indices) in [("input", subgraph.inputs()), ("output", subgraph.outputs())] {
+        let indices = indices
+            .filter(|indices| !indices.is_empty())
+            .ok_or_else(|| format!("the model has no {}s", kind))?;
+        for (i, index) in indices.iter().enumerate() {
+            let location = format!("model {} {}", kind, i);
+            validate_tensor(tensors, buffers, index, &location, TensorKind::Boundary)?;
+        }
+    }
+
+    for (i, operator) in operators.iter().enumerate() {
+        if operator.opcode_index() as usize >= operator_codes.len() {
+            return Err(format!(
+                "operator {} refers to the missing operator code {}",
+                i,
+                operator.opcode_index()
+            ));
+        }
+        let code = builtin_code(operator_codes, operator);
+        let location = format!("operator {} ({:?})", i, code);
+        // Unsupported operators are reported by the compiler
+        let Some((input_kinds, output_kind, options)) = specification(code) else {
+            continue;
+        };
+        let inputs = operator
+            .inputs()
+            .ok_or_else(|| format!("{} has no inputs", location))?;
+        let required = input_kinds
+            .iter()
+            .filter(|kind| !kind.is_optional())
+            .count();
+        if inputs.len() < required {
+            return Err(format!(
+                "{} has {} inputs, expected {}",
+                location,
+                inputs.len(),
+                required
+            ));
+        }
+        for (j, &kind) in input_kinds.iter().enumerate() {
+            if kind.is_optional() && (j >= inputs.len() || inputs.get(j) < 0) {
+                continue;
+            }
+            let input_location = format!("{}: input {}", location, j);
+            validate_tensor(tensors, buffers, inputs.get(j), &input_location, kind)?;
+        }
+        let output = operator
+            .outputs()
+            .filter(|outputs| !outputs.is_empty())
+            .ok_or_else(|| format!("{} has no outputs", location))?
+            .get(0);
+        let output_location = format!("{}: output 0", location);
+        validate_tensor(tensors, buffers, output, &output_location, output_kind)?;
+        if let Some(options) = options {
+            if operator.builtin_options_type() != options || operator.builtin_options().is_none() {
+                return Err(format!("{} has no {:?}", location, options));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the kinds of the inputs required by the given operator and the kind of its output,
+/// along with the type of its required options (if any).
+/// Returns [`None`] if the operator is not supported.
+///
+/// # Arguments
+/// * `code` - The builtin operator as a [`BuiltinOperator`]
+///
+fn specification(
+    code: BuiltinOperator,
+) -> Option<(&'static [TensorKind], TensorKind, Option<BuiltinOptions>)> {
+    use TensorKind::*;
+    Some(match code {
+        BuiltinOperator::FULLY_CONNECTED => (
+            &[Variable, Weights, Bias],
+            Variable,
+            Some(BuiltinOptions::FullyConnectedOptions),
+        ),
+        BuiltinOperator::CONV_2D => (
+            &[Variable, Weights, Bias],
+            Variable,
+            Some(BuiltinOptions::Conv2DOptions),
+        ),
+        BuiltinOperator::DEPTHWISE_CONV_2D => (
+            &[Variable, Weights, Bias],
+            Variable,
+            Some(BuiltinOptions::DepthwiseConv2DOptions),
+        ),
+        BuiltinOperator::AVERAGE_POOL_2D | BuiltinOperator::MAX_POOL_2D => {
+            (&[Variable], Variable, Some(BuiltinOptions::Pool2DOptions))
+        }
+        BuiltinOperator::ADD => (
+            &[Variable, Variable],
+            Variable,
+            Some(BuiltinOptions::AddOptions),
+        ),
+        BuiltinOperator::SOFTMAX => (&[Variable], Variable, Some(BuiltinOptions::SoftmaxOptions)),
+        BuiltinOperator::RESHAPE => (&[Variable], Variable, None),
+        BuiltinOperator::LOGISTIC
+        | BuiltinOperator::TANH
+        | BuiltinOperator::EXP
+        | BuiltinOperator::LOG
+        | BuiltinOperator::SQRT
+        | BuiltinOperator::RSQRT
+        | BuiltinOperator::ABS
+        | BuiltinOperator::NEG
+        | BuiltinOperator::FLOOR
+        | BuiltinOperator::ELU
+        | BuiltinOperator::GELU
+        | BuiltinOperator::HARD_SWISH => (&[Elementwise], Elementwise, None),
+        BuiltinOperator::QUANTIZE => (&[Boundary], Elementwise, None),
+        BuiltinOperator::DEQUANTIZE => (&[Elementwise], Boundary, None),
+        _ => return None,
+    })
+}
+
+/// Validates the tensor with the given index, used at the given location of the model.
+/// The tensor must have a shape, a type allowed for its kind and, unless it is a float tensor, its
+/// quantization parameters.
+/// If the tensor is stored in the model, the size of its data must match its shape.
+///
+/// # Arguments
+/// * `tensors` - The model tensors as a [`Vector<ForwardsUOffset<Tensor>>`]
+/// * `buffers` - The model buffers as a [`Vector<ForwardsUOffset<Buffer>>`]
+/// * `index` - The index of the tensor
+/// * `location` - The description of where the tensor is used
+/// * `kind` - The kind of the tensor as a [`TensorKind`]
+///
+fn validate_tensor(
+    tensors: Vector<ForwardsUOffset<Tensor>>,
+    buffers: Vector<ForwardsUOffset<Buffer>>,
+    index: i32,
+    location: &str,
+    kind: TensorKind,
+) -> Result<(), String> {
+    let tensor = usize::try_from(index)
+        .ok()
+        .filter(|&index| index < tensors.len())
+        .map(|index| tensors.get(index))
+        .ok_or_else(|| format!("{} refers to the missing tensor {}", location, index))?;
+    let name = format!(
+        "{} '{}' (tensor {})",
+        location,
+        tensor.name().unwrap_or_default(),
+        index
+    );
+    let shape = tensor
+        .shape()
+        .ok_or_else(|| format!("{} has no shape", name))?;
+    if let Some(dim) = shape.iter().find(|&dim| dim <= 0) {
+        return Err(format!("{} has the non-positive dimension {}", name, dim));
+    }
+    if !kind.types().contains(&tensor.type_()) {
+        let types: Vec<_> = kind.types().iter().map(|t| format!("{:?}", t)).collect();
+        return Err(format!(
+            "{} has type {:?}, expected {}",
+            name,
+            tensor.type_(),
+            types.join(" or ")
+        ));
+    }
+    if tensor.type_() != TensorType::FLOAT32 {
+        let quantization = tensor
+            .quantization()
+            .ok_or_else(|| format!("{} has no quantization parameters", name))?;
+        if quantization.scale().is_none_or(|scale| scale.is_empty()) {
+            return Err(format!("{} has no quantization scale", name));
+        }
+        if quantization
+            .zero_point()
+            .is_none_or(|zero_point| zero_point.is_empty())
+        {
+            return Err(format!("{} has no quantization zero point", name));
+        }
+    }
+    if tensor.buffer() as usize >= buffers.len() {
+        return Err(format!(
+            "{} refers to the missing buffer {}",
+            name,
+            tensor.buffer()
+        ));
+    }
+    match buffers.get(tensor.buffer() as usize).data() {
+        Some(data) if !data.is_empty() => {
+            let element_size = match tensor.type_() {
+                TensorType::INT8 | TensorType::UINT8 => 1,
+                TensorType::INT16 => 2,
+                TensorType::INT32 | TensorType::FLOAT32 => 4,
+                _ => return Ok(()),
+            };
+            let len = shape.iter().product::<i32>() as usize * element_size;
+            if data.len() != len {
+                return Err(format!(
+                    "{} has {} bytes of data, expected {}",
+                    name,
+                    data.len(),
+                    len
+                ));
+            }
+        }
+        _ if kind.is_constant() => return Err(format!("{} has no data", name)),
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use flatbuffers::FlatBufferBuilder;
+
+    use super::*;
+    use crate::tflite_flatbuffers::tflite::{
+        finish_model_buffer, BufferArgs, FullyConnectedOptions, FullyConnectedOptionsArgs,
+        ModelArgs, OperatorArgs, OperatorCodeArgs, QuantizationParameters,
+        QuantizationParametersArgs, SubGraph, SubGraphArgs, TensorArgs,
+    };
+
+    /// Represents the properties of a `FullyConnected` model, altered to make it malformed.
+    struct TestModel {
+        opcode_index: u32,
+        operator_code: (i8, BuiltinOperator),
+        inputs: Vec<i32>,
+        input_shape: Vec<i32>,
+        input_type: TensorType,
+        options: bool,
+        weights_quantization: bool,
+        weights_data: Vec<u8>,
+    }
+
+    impl Default for TestModel {
+        fn default() -> Self {
+            Self {
+                opcode_index: 0,
+                operator_code: (
+                    BuiltinOperator::FULLY_CONNECTED.0 as i8,
+                    BuiltinOperator::FULLY_CONNECTED,
+                ),
+                inputs: vec![0, 1, 2],
+                input_shape: vec![1, 2],
+                input_type: TensorType::INT8,
+                options: true,
+                weights_quantization: true,
+                weights_data: vec![1, 2],
+            }
+        }
+    }
+
+    impl TestModel {
+        /// Builds the flatbuffer of the model.
+        fn build(&self) -> Vec<u8> {
+            let mut builder = FlatBufferBuilder::new();
+            let tensor_specs = [
+                ("input", self.input_shape.clone(), self.input_type, 0, true),
+                (
+                    "weights",
+                    vec![1, 2],
+                    TensorType::INT8,
+                    1,
+                    self.weights_quantization,
+                ),
+                ("bias", vec![1], TensorType::INT32, 2, true),
+                ("output", vec![1, 1], TensorType::INT8, 0, true),
+            ];
+            let tensors: Vec<_> = tensor_specs
+                .iter()
+                .map(|(name, shape, type_, buffer, quantized)| {
+                    let name = builder.create_string(name);
+                    let shape = builder.create_vector(shape);
+                    let quantization = quantized.then(|| {
+                        let scale = builder.create_vector(&[0.5f32]);
+                        let zero_point = builder.create_vector(&[0i64]);
+                        QuantizationParameters::create(
+                            &mut builder,
+                            &QuantizationParametersArgs {
+                                scale: Some(scale),
+                                zero_point: Some(zero_point),
+                                ..Default::default()
+                            },
+                        )
+                    });
+                    Tensor::create(
+                        &mut builder,
+                        &TensorArgs {
+                            shape: Some(shape),
+                            type_: *type_,
+                            buffer: *buffer,
+                            name: Some(name),
+                            quantization,
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect();
+            let tensors = builder.create_vector(&tensors);
+            let buffers: Vec<_> = [vec![], self.weights_data.clone(), vec![3, 0, 0, 0]]
+                .iter()
+                .map(|data| {
+                    let data = (!data.is_empty()).then(|| builder.create_vector(data));
+                    Buffer::create(&mut builder, &BufferArgs { data })
+                })
+                .collect();
+            let buffers = builder.create_vector(&buffers);
+            let options = self.options.then(|| {
+                FullyConnectedOptions::create(&mut builder, &FullyConnectedOptionsArgs::default())
+                    .as_union_value()
+            });
+            let inputs = builder.create_vector(&self.inputs);
+            let outputs = builder.create_vector(&[3]);
+            let operator = Operator::create(
+                &mut builder,
+                &OperatorArgs {
+                    opcode_index: self.opcode_index,
+                    inputs: Some(inputs),
+                    outputs: Some(outputs),
+                    builtin_options_type: if self.options {
+                        BuiltinOptions::FullyConnectedOptions
+                    } else {
+                        BuiltinOptions::NONE
+                    },
+                    builtin_options: options,
+                    ..Default::default()
+                },
+            );
+            let operators = builder.create_vector(&[operator]);
+            let inputs = builder.create_vector(&[0]);
+            let outputs = builder.create_vector(&[3]);
+            let subgraph = SubGraph::create(
+                &mut builder,
+                &SubGraphArgs {
+                    tensors: Some(tensors),
+                    inputs: Some(inputs),
+                    outputs: Some(outputs),
+                    operators: Some(operators),
+                    name: None,
+                },
+            );
+            let subgraphs = builder.create_vector(&[subgraph]);
+            let operator_code = OperatorCode::create(
+                &mut builder,
+                &OperatorCodeArgs {
+                    deprecated_builtin_code: self.operator_code.0,
+                    builtin_code: self.operator_code.1,
+                    ..Default::default()
+                },
+            );
+            let operator_codes = builder.create_vector(&[operator_code]);
+            let model = Model::create(
+                &mut builder,
+                &ModelArgs {
+                    version: 3,
+                    operator_codes: Some(operator_codes),
+                    subgraphs: Some(subgraphs),
+                    buffers: Some(buffers),
+                    ..Default::default()
+                },
+            );
+            finish_model_buffer(&mut builder, model);
+            builder.finished_data().to_vec()
+        }
+
+        /// Returns the builtin operator of the operator of the model.
+        fn builtin_code(&self) -> BuiltinOperator {
+            let buf = self.build();
+            let model = load(&buf).unwrap();
+            let operator = model
+                .subgraphs()
+                .unwrap()
+                .get(0)
+                .operators()
+                .unwrap()
+                .get(0);
+            builtin_code(model.operator_codes().unwrap(), operator)
+        }
+
+        /// Returns the error of loading the model.
+        fn error(&self) -> String {
+            load(&self.build()).err().unwrap()
+        }
+    }
+
+    #[test]
+    fn load_valid() {
+        assert!(load(&TestModel::default().build()).is_ok());
+    }
+
+    #[test]
+    fn builtin_code_deprecated() {
+        let model = TestModel {
+            operator_code: (
+                BuiltinOperator::FULLY_CONNECTED.0 as i8,
+                BuiltinOperator::ADD,
+            ),
+            ..Default::default()
+        };
+        assert_eq!(model.builtin_code(), BuiltinOperator::FULLY_CONNECTED);
+    }
+
+    #[test]
+    fn builtin_code_extended() {
+        let model = TestModel {
+            operator_code: (
+                BuiltinOperator::PLACEHOLDER_FOR_GREATER_OP_CODES.0 as i8,
+                BuiltinOperator::GELU,
+            ),
+            ..Default::default()
+        };
+        assert_eq!(model.builtin_code(), BuiltinOperator::GELU);
+    }
+
+    #[test]
+    fn builtin_code_dilate() {
+        let model = TestModel {
+            operator_code: (
+                BuiltinOperator::PLACEHOLDER_FOR_GREATER_OP_CODES.0 as i8,
+                BuiltinOperator(203),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(model.builtin_code(), BuiltinOperator::DILATE);
+        assert_eq!(format!("{:?}", model.builtin_code()), "DILATE");
+    }
+
+    #[test]
+    fn load_truncated() {
+        let buf = TestModel::default().build();
+        assert!(load(&buf[..buf.len() / 2])
+            .err()
+            .unwrap()
+            .starts_with("invalid flatbuffer"));
+    }
+
+    #[test]
+    fn load_missing_operator_code() {
+        let model = TestModel {
+            opcode_index: 5,
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 refers to the missing operator code 5"
+        );
+    }
+
+    #[test]
+    fn load_missing_inputs() {
+        let model = TestModel {
+            inputs: vec![0],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED) has 1 inputs, expected 2"
+        );
+    }
+
+    #[test]
+    fn load_missing_bias() {
+        for inputs in [vec![0, 1], vec![0, 1, -1]] {
+            let model = TestModel {
+                inputs,
+                ..Default::default()
+            };
+            assert!(load(&model.build()).is_ok());
+        }
+    }
+
+    #[test]
+    fn load_missing_tensor() {
+        let model = TestModel {
+            inputs: vec![0, 9, 2],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED): input 1 refers to the missing tensor 9"
+        );
+    }
+
+    #[test]
+    fn load_missing_options() {
+        let model = TestModel {
+            options: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED) has no FullyConnectedOptions"
+        );
+    }
+
+    #[test]
+    fn load_missing_quantization() {
+        let model = TestModel {
+            weights_quantization: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED): input 1 'weights' (tensor 1) has no quantization parameters"
+        );
+    }
+
+    #[test]
+    fn load_wrong_type() {
+        let model = TestModel {
+            inputs: vec![0, 2, 2],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED): input 1 'bias' (tensor 2) has type INT32, expected INT8 or UINT8"
+        );
+    }
+
+    #[test]
+    fn load_non_positive_dimension() {
+        let model = TestModel {
+            input_shape: vec![1, 0],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "model input 0 'input' (tensor 0) has the non-positive dimension 0"
+        );
+    }
+
+    #[test]
+    fn load_int16() {
+        let model = TestModel {
+            operator_code: (BuiltinOperator::LOGISTIC.0 as i8, BuiltinOperator::LOGISTIC),
+            inputs: vec![0],
+            input_type: TensorType::INT16,
+            ..Default::default()
+        };
+        assert!(load(&model.build()).is_ok());
+    }
+
+    #[test]
+    fn load_wrong_type_int16() {
+        let model = TestModel {
+            input_type: TensorType::INT16,
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED): input 0 'input' (tensor 0) has type INT16, expected INT8 or UINT8"
+        );
+    }
+
+    #[test]
+    fn load_missing_data() {
+        let model = TestModel {
+            weights_data: vec![],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED): input 1 'weights' (tensor 1) has no data"
+        );
+    }
+
+    #[test]
+    fn load_wrong_data_size() {
+        let model = TestModel {
+            weights_data: vec![1, 2, 3],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.error(),
+            "operator 0 (FULLY_CONNECTED): input 1 'weights' (tensor 1) has 3 bytes of data, expected 2"
+        );
+    }
+}
@@ -1,15 +1,27 @@
-use crate::quantize::{quantize, Quantized};
+use crate::quantize::{clamp, look_up, quantize_uncounted, saturate, Quantized};
 use core::cmp::{max, min};
 use libm::expf;
+use simba::scalar::SupersetOf;
 
 /// Represents the supported activation functions.
-pub enum FusedActivation {
+/// The activation functions depending on the output quantization hold the quantized values
+/// computed by the compiler, so that they are applied with integer arithmetic only.
+pub enum FusedActivation<T: 'static> {
     /// The identity activation function.
     None,
     /// The Rectified Linear Unit (ReLU) function.
     Relu,
-    /// The Rectified Linear Unit 6 (ReLU6) function.
-    Relu6,
+    /// The Rectified Linear Unit 6 (ReLU6) function, holding the quantized value of 6.
+    Relu6(T),
+    /// The Rectified Linear Unit clipped to \[-1, 1\] (ReLU_N1_TO_1) function, holding the
+    /// quantized values of -1 and 1.
+    ReluN1To1(T, T),
+    /// The hyperbolic tangent (tanh) function, holding the table of its quantized results for
+    /// every quantized input.
+    Tanh(&'static [T; 256]),
+    /// The sign bit function, returning 1 for the negative values and 0 otherwise, holding the
+    /// quantized value of 1.
+    SignBit(T),
 }
 
 /// Performs the Rectified Linear Unit (ReLU) activation function.
@@ -30,7 +42,46 @@ pub fn relu<T: Quantized>(input: T, zero_point: T) -> T {
 /// * `zero_point` - The quantization zero point
 ///
 pub fn relu6<T: Quantized>(input: T, scale: f32, zero_point: T) -> T {
-    min(relu(input, zero_point), quantize(6., scale, zero_point))
+    min(
+        relu(input, zero_point),
+        quantize_uncounted(6., scale, zero_point),
+    )
+}
+
+/// Applies the given fused activation function to the given requantized value, saturating the
+/// result to the range of `T`.
+/// The activation function is applied before the saturation, so that the values clipped by the
+/// activation function are not counted as saturated.
+///
+/// # Arguments
+/// * `input` - The requantized value
+/// * `activation` - The fused activation function as a [`FusedActivation`]
+/// * `zero_point` - The quantization zero point
+///
+pub fn activate<T: Quantized>(input: i32, activation: &FusedActivation<T>, zero_point: T) -> T {
+    let zero_point_i32 = i32::from_subset(&zero_point);
+    match activation {
+        FusedActivation::None => saturate(input),
+        FusedActivation::Relu => saturate(max(input, zero_point_i32)),
+        FusedActivation::Relu6(six) => {
+            saturate(min(max(input, zero_point_i32), i32::from_subset(six)))
+        }
+        FusedActivation::ReluN1To1(minus_one, one) => saturate(min(
+            max(input, i32::from_subset(minus_one)),
+            i32::from_subset(one),
+        )),
+        // The table covers the range of `T`, so the input is clamped to it before the look-up
+        FusedActivation::Tanh(table) => {
+            look_up(T::from_superset_unchecked(&clamp::<T>(input)), table)
+        }
+        FusedActivation::SignBit(one) => {
+            if input < zero_point_i32 {
+                *one
+            } else {
+                zero_point
+            }
+        }
+    }
 }
 
 /// Performs the Softmax activation function.
@@ -42,11 +93,16 @@ pub fn relu6<T: Quantized>(input: T, scale: f32, zero_point: T) -> T {
 /// * `zero_point` - The quantization zero point
 ///
 pub fn softmax<T: Quantized>(input: f32, sum: f32, scale: f32, zero_point: T) -> T {
-    quantize(expf(input) / sum, scale, zero_point)
+    // The probability of 1 is not representable with the usual output quantization
+    quantize_uncounted(expf(input) / sum, scale, zero_point)
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
+    use std::boxed::Box;
+
     use super::*;
 
     const SCALE: f32 = 0.1;
@@ -83,6 +139,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn activate_saturated() {
+        assert_eq!(activate(1000, &FusedActivation::None, ZERO_POINT), i8::MAX);
+        assert_eq!(
+            activate(-1000, &FusedActivation::Relu, ZERO_POINT),
+            ZERO_POINT
+        );
+        assert_eq!(
+            activate(1000, &FusedActivation::Relu6(62), ZERO_POINT),
+            RELU6_SATURATION_POINT
+        );
+    }
+
+    #[test]
+    fn activate_relu_n1_to_1() {
+        let activation = FusedActivation::ReluN1To1(-8, 12);
+        assert_eq!(activate(-1000, &activation, ZERO_POINT), -8);
+        assert_eq!(
+            activate(RELU_ACTIVE_INPUT.into(), &activation, ZERO_POINT),
+            RELU_ACTIVE_INPUT
+        );
+        assert_eq!(activate(1000, &activation, ZERO_POINT), 12);
+    }
+
+    #[test]
+    fn activate_tanh() {
+        // The table of the quantized results of tanh for every quantized input
+        let table: &'static mut [i8; 256] = Box::leak(Box::new([0; 256]));
+        for (v, y) in (-128..=127).zip(table.iter_mut()) {
+            let x = SCALE * (v - i32::from(ZERO_POINT)) as f32;
+            *y = quantize_uncounted(libm::tanhf(x), SCALE, ZERO_POINT);
+        }
+        let activation = FusedActivation::Tanh(table);
+        assert_eq!(activate(-1000, &activation, ZERO_POINT), -8);
+        assert_eq!(
+            activate(ZERO_POINT.into(), &activation, ZERO_POINT),
+            ZERO_POINT
+        );
+        // tanh(0.5) = 0.46211716
+        assert_eq!(activate(7, &activation, ZERO_POINT), 7);
+        assert_eq!(activate(1000, &activation, ZERO_POINT), 12);
+    }
+
+    #[test]
+    fn activate_sign_bit() {
+        let activation = FusedActivation::SignBit(12);
+        assert_eq!(
+            activate(RELU_INACTIVE_INPUT.into(), &activation, ZERO_POINT),
+            12
+        );
+        assert_eq!(
+            activate(ZERO_POINT.into(), &activation, ZERO_POINT),
+            ZERO_POINT
+        );
+        assert_eq!(activate(1000, &activation, ZERO_POINT), ZERO_POINT);
+    }
+
     #[test]
     fn softmax_active() {
         assert_eq!(
@@ -0,0 +1,94 @@
+use core::cell::UnsafeCell;
+use core::mem::{align_of, size_of};
+
+/// The alignment of the [`Arena`] and of the offsets of the values placed into it.
+pub const ARENA_ALIGNMENT: usize = 16;
+
+/// Represents the memory arena holding the intermediate tensors of a model.
+/// The offsets of the tensors are planned by the MicroFlow compiler, so that tensors whose
+/// lifetimes do not overlap share the same memory region.
+///
+/// The operators write their outputs directly into the arena, so the size of the stack does not
+/// grow with the size of the tensors.
+/// Since the arena holds all the tensors, it should be allocated statically, e.g.:
+/// ```rust ignore
+/// static mut ARENA: Arena<{ MyModel::ARENA_SIZE }> = Arena::new();
+/// ```
+#[repr(C, align(16))]
+pub struct Arena<const SIZE: usize> {
+    buffer: UnsafeCell<[u8; SIZE]>,
+}
+
+impl<const SIZE: usize> Arena<SIZE> {
+    /// Builds a zero-initialized [`Arena`].
+    pub const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new([0; SIZE]),
+        }
+    }
+
+    /// Reserves the region of the arena at the given offset for a value.
+    /// Returns a mutable reference to the value, holding the bytes previously in the region.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the value in the arena
+    /// * `size` - The size of the region reserved for the value
+    ///
+    /// # Panics
+    /// Panics if the value does not fit in the reserved region, if the region exceeds the arena,
+    /// or if the offset is not properly aligned for the value.
+    ///
+    /// # Safety
+    /// Any bit pattern must be a valid value `V`, as for the quantized tensors.
+    /// The reserved region must not overlap with the region of any other value of the arena that
+    /// is still in use.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn slot<V>(&self, offset: usize, size: usize) -> &mut V {
+        assert!(size_of::<V>() <= size && offset + size <= SIZE);
+        assert!(align_of::<V>() <= ARENA_ALIGNMENT && offset % align_of::<V>() == 0);
+        &mut *self.buffer.get().cast::<u8>().add(offset).cast::<V>()
+    }
+}
+
+impl<const SIZE: usize> Default for Arena<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::matrix;
+
+    use super::*;
+    use crate::tensor::Tensor2D;
+
+    #[test]
+    fn arena_slot() {
+        let arena = Arena::<64>::new();
+        let tensor_1: &mut Tensor2D<i8, 2, 3, 1> = unsafe { arena.slot(0, 16) };
+        assert_eq!(tensor_1, &Tensor2D::default());
+        tensor_1.buffer = matrix![1, 2, 3; 4, 5, 6];
+        tensor_1.scale = [0.7];
+        tensor_1.zero_point = [8];
+        let tensor_2: &mut Tensor2D<i8, 2, 2, 1> = unsafe { arena.slot(16, 16) };
+        tensor_2.buffer = matrix![9, 10; 11, 12];
+        tensor_2.scale = [0.13];
+        tensor_2.zero_point = [14];
+        assert_eq!(
+            tensor_1,
+            &Tensor2D::new(matrix![1i8, 2, 3; 4, 5, 6], [0.7], [8])
+        );
+        assert_eq!(
+            tensor_2,
+            &Tensor2D::new(matrix![9i8, 10; 11, 12], [0.13], [14])
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn arena_slot_overflow() {
+        let arena = Arena::<16>::new();
+        unsafe { arena.slot::<[u8; 4]>(16, 16) };
+    }
+}
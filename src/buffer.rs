@@ -1,3 +1,5 @@
+use core::mem::size_of;
+
 use nalgebra::SMatrix;
 
 /// Represents a 2-dimensional buffer.
@@ -14,3 +16,62 @@ pub type Buffer4D<
     const COLUMNS: usize,
     const CHANNELS: usize,
 > = [Buffer2D<[T; CHANNELS], ROWS, COLUMNS>; BATCHES];
+
+/// Represents the raw bytes of a buffer `B`, aligned as the buffer itself.
+#[repr(C)]
+union RawBuffer<B: Copy, const N: usize> {
+    bytes: [u8; N],
+    buffer: B,
+}
+
+/// Reinterprets the given bytes as a buffer `B`.
+/// When evaluated in a constant, the bytes are laid out and aligned at compile time, so no copy
+/// is made at runtime.
+/// The bytes must follow the memory layout of `B`, which is column-major for the [`Buffer2D`].
+/// The number of bytes must match the size of the buffer, otherwise the evaluation panics.
+///
+/// # Arguments
+/// * `bytes` - The bytes of the buffer, in the memory layout of `B`
+///
+/// # Safety
+/// The bytes must be a valid value of `B`, which always holds for buffers of `i8` and `u8`.
+///
+pub const unsafe fn from_raw_bytes<B: Copy, const N: usize>(bytes: [u8; N]) -> B {
+    assert!(
+        size_of::<B>() == N,
+        "the size of the bytes must match the size of the buffer"
+    );
+    RawBuffer { bytes }.buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::matrix;
+
+    use super::*;
+
+    #[test]
+    fn from_raw_bytes_2d() {
+        const BUFFER: Buffer2D<i8, 2, 3> = unsafe { from_raw_bytes(*b"\x01\x04\x02\x05\x03\xfa") };
+        assert_eq!(
+            BUFFER,
+            matrix![
+                1, 2, 3;
+                4, 5, -6
+            ]
+        );
+    }
+
+    #[test]
+    fn from_raw_bytes_4d() {
+        const BUFFER: Buffer4D<u8, 2, 1, 2, 2> =
+            unsafe { from_raw_bytes(*b"\x01\x02\x03\x04\x05\x06\x07\x08") };
+        assert_eq!(BUFFER, [matrix![[1, 2], [3, 4]], matrix![[5, 6], [7, 8]]]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_raw_bytes_size_mismatch() {
+        let _: Buffer2D<i8, 2, 3> = unsafe { from_raw_bytes(*b"\x01\x02") };
+    }
+}
@@ -9,6 +9,7 @@
 pub use microflow_macros::*;
 
 pub mod activation;
+pub mod arena;
 pub mod buffer;
 pub mod ops;
 pub mod quantize;
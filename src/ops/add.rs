@@ -0,0 +1,144 @@
+use simba::scalar::SupersetOf;
+
+use crate::activation::{activate, FusedActivation};
+use crate::quantize::{Quantized, Requantizer};
+use crate::tensor::TensorBroadcast;
+
+pub struct AddOptions<T: 'static> {
+    pub fused_activation: FusedActivation<T>,
+}
+
+/// Performs the Add operation.
+/// Writes into the output tensor the element-wise sum of the inputs, broadcasted following the
+/// TensorFlow Lite rules.
+///
+/// # Arguments
+/// * `input_1` - The first input tensor
+/// * `input_2` - The second input tensor
+/// * `output_scale` - The scale of the resulting output tensor
+/// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `options` - Operator's options as an [`AddOptions`] struct
+/// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The output tensor
+///
+pub fn add<T: Quantized, R: Requantizer, InputT1, InputT2, OutputT>(
+    input_1: &InputT1,
+    input_2: &InputT2,
+    output_scale: [f32; 1],
+    output_zero_point: [T; 1],
+    options: AddOptions<T>,
+    constants: (R, R, R),
+    output: &mut OutputT,
+) where
+    InputT1: TensorBroadcast<T, InputT2, OutputT>,
+{
+    let zero_points = input_1.zero_points(input_2);
+    input_1.broadcast_into(
+        input_2,
+        |x1, x2| {
+            // Rescale both inputs to the output quantization and sum them
+            let y = R::requantize_sum(
+                (i32::from_subset(&x1), i32::from_subset(&x2)),
+                (zero_points.0, zero_points.1, output_zero_point[0]),
+                constants,
+            );
+            // Apply the fused activation function (if any), saturating the output
+            activate(y, &options.fused_activation, output_zero_point[0])
+        },
+        output_scale,
+        output_zero_point,
+        output,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::matrix;
+
+    use crate::tensor::{Tensor2D, Tensor4D};
+
+    use super::*;
+
+    const INPUT_1_2D: Tensor2D<i8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            1, 2, 3;
+            4, 5, 6
+        ],
+        scale: [0.7],
+        zero_point: [8],
+    };
+    const INPUT_2_2D: Tensor2D<i8, 1, 3, 1> = Tensor2D {
+        buffer: matrix![9, 10, 11],
+        scale: [0.12],
+        zero_point: [13],
+    };
+    const INPUT_1_4D: Tensor4D<i8, 1, 2, 3, 2, 1> = Tensor4D {
+        buffer: [matrix![
+            [1, 2], [3, 4],  [5,  6];
+            [7, 8], [9, 10], [11, 12]
+        ]],
+        scale: [0.13],
+        zero_point: [-14],
+    };
+    const INPUT_2_4D: Tensor4D<i8, 1, 1, 1, 2, 1> = Tensor4D {
+        buffer: [matrix![[-15, 26]]],
+        scale: [0.17],
+        zero_point: [18],
+    };
+    const OUTPUT_SCALE: [f32; 1] = [0.19];
+    const OUTPUT_ZERO_POINT: [i8; 1] = [20];
+    const OPTIONS_2D: AddOptions<i8> = AddOptions {
+        fused_activation: FusedActivation::None,
+    };
+    const OPTIONS_4D: AddOptions<i8> = AddOptions {
+        fused_activation: FusedActivation::Relu,
+    };
+    const CONSTANTS_2D: (f32, f32, f32) = (3.684_210_5, 0.631_578_9, -17.684_212);
+    const CONSTANTS_4D: (f32, f32, f32) = (0.684_210_5, 0.894_736_9, 13.473_684);
+    const OUTPUT_2D: Tensor2D<i8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            -8, -4, 0;
+            3,  7,  11
+        ],
+        scale: [0.19],
+        zero_point: [20],
+    };
+    const OUTPUT_4D: Tensor4D<i8, 1, 2, 3, 2, 1> = Tensor4D {
+        buffer: [matrix![
+            [20, 38], [20, 39], [20, 41];
+            [20, 42], [20, 44], [20, 45]
+        ]],
+        scale: [0.19],
+        zero_point: [20],
+    };
+
+    #[test]
+    fn add_layer_2d() {
+        let mut output: Tensor2D<i8, 2, 3, 1> = Tensor2D::default();
+        add(
+            &INPUT_1_2D,
+            &INPUT_2_2D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS_2D,
+            CONSTANTS_2D,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_2D);
+    }
+
+    #[test]
+    fn add_layer_4d() {
+        let mut output: Tensor4D<i8, 1, 2, 3, 2, 1> = Tensor4D::default();
+        add(
+            &INPUT_1_4D,
+            &INPUT_2_4D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS_4D,
+            CONSTANTS_4D,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_4D);
+    }
+}
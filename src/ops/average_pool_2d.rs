@@ -1,22 +1,20 @@
 use core::array;
-use libm::roundf;
 
 use nalgebra::Const;
 use simba::scalar::SupersetOf;
 
-use crate::activation::{relu, relu6, FusedActivation};
-use crate::buffer::Buffer2D;
-use crate::quantize::Quantized;
+use crate::activation::{activate, FusedActivation};
+use crate::quantize::{Quantized, Requantizer};
 use crate::tensor::{Tensor4D, TensorView, TensorViewPadding};
 
-pub struct AveragePool2DOptions {
-    pub fused_activation: FusedActivation,
+pub struct AveragePool2DOptions<T: 'static> {
+    pub fused_activation: FusedActivation<T>,
     pub view_padding: TensorViewPadding,
     pub strides: (usize, usize),
 }
 
 /// Performs the AveragePool2D operation.
-/// Returns a 4-dimensional output tensor containing the result of the operation.
+/// Writes the result of the operation into the 4-dimensional output tensor.
 ///
 /// # Arguments
 /// * `input` - The 4-dimensional input tensor
@@ -25,9 +23,12 @@ pub struct AveragePool2DOptions {
 /// * `output_zero_point` - The zero point of the resulting output tensor
 /// * `options` - Operator's options as an [`AveragePool2DOptions`] struct
 /// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The 4-dimensional output tensor
 ///
 pub fn average_pool_2d<
     T: Quantized,
+    R: Requantizer,
+    const BATCHES: usize,
     const INPUT_ROWS: usize,
     const INPUT_COLS: usize,
     const INPUT_CHANS: usize,
@@ -36,33 +37,37 @@ pub fn average_pool_2d<
     const OUTPUT_ROWS: usize,
     const OUTPUT_COLS: usize,
 >(
-    input: Tensor4D<T, 1, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
+    input: &Tensor4D<T, BATCHES, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
     _filter_shape: (Const<FILTER_ROWS>, Const<FILTER_COLS>),
     output_scale: [f32; 1],
     output_zero_point: [T; 1],
-    options: AveragePool2DOptions,
-    constants: (f32, f32),
-) -> Tensor4D<T, 1, OUTPUT_ROWS, OUTPUT_COLS, INPUT_CHANS, 1> {
-    let output = [Buffer2D::from_fn(|i, j| {
-        // Extract the view using the view extraction algorithm
-        let view: TensorView<T, FILTER_ROWS, FILTER_COLS, INPUT_CHANS> =
-            input.view((i, j), 0, options.view_padding, options.strides);
-        // Compute the average pooling for each channel
-        array::from_fn(|c| {
-            let x = 1. / view.len as f32
-                * view
-                    .buffer
-                    .fold(0i32, |acc, a| acc + i32::from_subset(&a[c])) as f32;
-            let y = T::from_superset_unchecked(&roundf(constants.0 * x + constants.1));
-            // Apply the fused activation function (if any)
-            match options.fused_activation {
-                FusedActivation::None => y,
-                FusedActivation::Relu => relu(y, output_zero_point[0]),
-                FusedActivation::Relu6 => relu6(y, output_scale[0], output_zero_point[0]),
+    options: AveragePool2DOptions<T>,
+    constants: (R, R::Bias),
+    output: &mut Tensor4D<T, BATCHES, OUTPUT_ROWS, OUTPUT_COLS, INPUT_CHANS, 1>,
+) {
+    for batch in 0..BATCHES {
+        for i in 0..OUTPUT_ROWS {
+            for j in 0..OUTPUT_COLS {
+                // Extract the view using the view extraction algorithm
+                let view: TensorView<T, FILTER_ROWS, FILTER_COLS, INPUT_CHANS> =
+                    input.view((i, j), batch, options.view_padding, options.strides, (1, 1));
+                // Compute the average pooling for each channel
+                output.buffer[batch][(i, j)] = array::from_fn(|c| {
+                    let x = view
+                        .buffer
+                        .fold(0i32, |acc, a| acc + i32::from_subset(&a[c]));
+                    let y =
+                        constants
+                            .0
+                            .requantize_mean(x, view.len, constants.1, output_zero_point[0]);
+                    // Apply the fused activation function (if any), saturating the output
+                    activate(y, &options.fused_activation, output_zero_point[0])
+                });
             }
-        })
-    })];
-    Tensor4D::new(output, output_scale, output_zero_point)
+        }
+    }
+    output.scale = output_scale;
+    output.zero_point = output_zero_point;
 }
 
 #[cfg(test)]
@@ -70,6 +75,7 @@ mod tests {
     use nalgebra::matrix;
 
     use super::*;
+    use crate::buffer::Buffer2D;
 
     const INPUT: Tensor4D<i8, 1, 2, 3, 2, 1> = Tensor4D {
         buffer: [matrix![
@@ -82,7 +88,7 @@ mod tests {
     const FILTER_SHAPE: (Const<2>, Const<3>) = (Const, Const);
     const OUTPUT_SCALE: [f32; 1] = [0.15];
     const OUTPUT_ZERO_POINT: [i8; 1] = [16];
-    const OPTIONS: AveragePool2DOptions = AveragePool2DOptions {
+    const OPTIONS: AveragePool2DOptions<i8> = AveragePool2DOptions {
         fused_activation: FusedActivation::None,
         view_padding: TensorViewPadding::Same,
         strides: (1, 1),
@@ -99,16 +105,42 @@ mod tests {
 
     #[test]
     fn average_pool_2d_layer() {
-        assert_eq!(
-            average_pool_2d(
-                INPUT,
-                FILTER_SHAPE,
-                OUTPUT_SCALE,
-                OUTPUT_ZERO_POINT,
-                OPTIONS,
-                CONSTANTS,
-            ),
-            OUTPUT
+        let mut output = Tensor4D::default();
+        average_pool_2d(
+            &INPUT,
+            FILTER_SHAPE,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT);
+    }
+
+    #[test]
+    fn average_pool_2d_layer_batches() {
+        // The second batch is at the zero point, i.e., it is averaged to the output zero point
+        let input = Tensor4D::new(
+            [INPUT.buffer[0], Buffer2D::from_element([14; 2])],
+            INPUT.scale,
+            INPUT.zero_point,
+        );
+        let expected = Tensor4D::new(
+            [OUTPUT.buffer[0], Buffer2D::from_element([16; 2])],
+            OUTPUT.scale,
+            OUTPUT.zero_point,
+        );
+        let mut output = Tensor4D::default();
+        average_pool_2d(
+            &input,
+            FILTER_SHAPE,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS,
+            &mut output,
         );
+        assert_eq!(output, expected);
     }
 }
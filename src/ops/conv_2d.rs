@@ -1,21 +1,21 @@
 use core::array;
-use libm::roundf;
 
 use simba::scalar::SupersetOf;
 
-use crate::activation::{relu, relu6, FusedActivation};
+use crate::activation::{activate, FusedActivation};
 use crate::buffer::Buffer2D;
-use crate::quantize::Quantized;
+use crate::quantize::{Quantized, Requantizer};
 use crate::tensor::{Tensor4D, TensorView, TensorViewPadding};
 
-pub struct Conv2DOptions {
-    pub fused_activation: FusedActivation,
+pub struct Conv2DOptions<T: 'static> {
+    pub fused_activation: FusedActivation<T>,
     pub view_padding: TensorViewPadding,
     pub strides: (usize, usize),
+    pub dilations: (usize, usize),
 }
 
 /// Performs the Conv2D operation.
-/// Returns a 4-dimensional output tensor containing the result of the operation.
+/// Writes the result of the operation into the 4-dimensional output tensor.
 ///
 /// # Arguments
 /// * `input` - The 4-dimensional input tensor
@@ -24,9 +24,12 @@ pub struct Conv2DOptions {
 /// * `output_zero_point` - The zero point of the resulting output tensor
 /// * `options` - Operator's options as an [`Conv2DOptions`] struct
 /// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The 4-dimensional output tensor
 ///
 pub fn conv_2d<
     T: Quantized,
+    R: Requantizer,
+    const BATCHES: usize,
     const INPUT_ROWS: usize,
     const INPUT_COLS: usize,
     const INPUT_CHANS: usize,
@@ -37,74 +40,88 @@ pub fn conv_2d<
     const OUTPUT_ROWS: usize,
     const OUTPUT_COLS: usize,
 >(
-    input: Tensor4D<T, 1, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
+    input: &Tensor4D<T, BATCHES, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
     filters: &Tensor4D<T, FILTERS_BATCHES, FILTERS_ROWS, FILTERS_COLS, INPUT_CHANS, FILTERS_QUANTS>,
     output_scale: [f32; 1],
     output_zero_point: [T; 1],
-    options: Conv2DOptions,
+    options: Conv2DOptions<T>,
     constants: (
-        Buffer2D<f32, FILTERS_BATCHES, 1>,
-        Buffer2D<f32, FILTERS_QUANTS, 1>,
+        Buffer2D<R::Bias, FILTERS_BATCHES, 1>,
+        Buffer2D<R, FILTERS_QUANTS, 1>,
     ),
-) -> Tensor4D<T, 1, OUTPUT_ROWS, OUTPUT_COLS, FILTERS_BATCHES, 1> {
-    let output = [Buffer2D::from_fn(|i, j| {
-        // Extract the view using the view extraction algorithm
-        let view: TensorView<T, FILTERS_ROWS, FILTERS_COLS, INPUT_CHANS> =
-            input.view((i, j), 0, options.view_padding, options.strides);
-        // Perform the convolution for each filter batch
-        array::from_fn(|b| {
-            let input_zero_point = i32::from_subset(&input.zero_point[0]);
-            let filters_zero_point = i32::from_subset(
-                &filters
-                    .zero_point
-                    .get(b)
-                    .copied()
-                    .unwrap_or(filters.zero_point[0]),
-            );
-            let x = (
-                // Perform the dot product between the input region and the filter
-                view.buffer.zip_fold(&filters.buffer[b], 0i32, |acc, v, f| {
-                    acc + v
-                        .iter()
-                        .zip(f.iter())
-                        .map(|(e1, e2)| i32::from_subset(e1) * i32::from_subset(e2))
-                        .sum::<i32>()
-                }),
-                // Perform the 3-dimensional component-sum of the view
-                view.buffer.fold(0i32, |acc, a| {
-                    acc + a.iter().fold(0i32, |acc, e| acc + i32::from_subset(e))
-                }) * filters_zero_point,
-            );
-            // Elaborate the constants
-            let constants = (
-                constants.0,
-                constants.1,
-                input_zero_point
-                    * filters.buffer[b].zip_fold(&view.mask, 0i32, |acc, f, m| {
-                        if m {
-                            acc + f.iter().fold(0i32, |acc, e| acc + i32::from_subset(e))
-                        } else {
-                            acc
-                        }
-                    }),
-                view.len as i32 * INPUT_CHANS as i32 * input_zero_point * filters_zero_point,
-            );
-            // Combine the constant values and the variants to obtain the output
-            let y = T::from_superset_unchecked(&roundf(
-                f32::from_subset(&output_zero_point[0])
-                    + constants.0[b]
-                    + constants.1.get(b).copied().unwrap_or(constants.1[0])
-                        * f32::from_subset(&(x.0 - x.1 - constants.2 + constants.3)),
-            ));
-            // Apply the fused activation function (if any)
-            match options.fused_activation {
-                FusedActivation::None => y,
-                FusedActivation::Relu => relu(y, output_zero_point[0]),
-                FusedActivation::Relu6 => relu6(y, output_scale[0], output_zero_point[0]),
+    output: &mut Tensor4D<T, BATCHES, OUTPUT_ROWS, OUTPUT_COLS, FILTERS_BATCHES, 1>,
+) {
+    for batch in 0..BATCHES {
+        for i in 0..OUTPUT_ROWS {
+            for j in 0..OUTPUT_COLS {
+                // Extract the view using the view extraction algorithm
+                let view: TensorView<T, FILTERS_ROWS, FILTERS_COLS, INPUT_CHANS> = input.view(
+                    (i, j),
+                    batch,
+                    options.view_padding,
+                    options.strides,
+                    options.dilations,
+                );
+                // Perform the convolution for each filter batch
+                output.buffer[batch][(i, j)] = array::from_fn(|b| {
+                    let input_zero_point = i32::from_subset(&input.zero_point[0]);
+                    let filters_zero_point = i32::from_subset(
+                        &filters
+                            .zero_point
+                            .get(b)
+                            .copied()
+                            .unwrap_or(filters.zero_point[0]),
+                    );
+                    let x = (
+                        // Perform the dot product between the input region and the filter
+                        view.buffer.zip_fold(&filters.buffer[b], 0i32, |acc, v, f| {
+                            acc + v
+                                .iter()
+                                .zip(f.iter())
+                                .map(|(e1, e2)| i32::from_subset(e1) * i32::from_subset(e2))
+                                .sum::<i32>()
+                        }),
+                        // Perform the 3-dimensional component-sum of the view
+                        view.buffer.fold(0i32, |acc, a| {
+                            acc + a.iter().fold(0i32, |acc, e| acc + i32::from_subset(e))
+                        }) * filters_zero_point,
+                    );
+                    // Elaborate the constants
+                    let constants = (
+                        constants.0,
+                        constants.1,
+                        input_zero_point
+                            * filters.buffer[b].zip_fold(&view.mask, 0i32, |acc, f, m| {
+                                if m {
+                                    acc + f.iter().fold(0i32, |acc, e| acc + i32::from_subset(e))
+                                } else {
+                                    acc
+                                }
+                            }),
+                        view.len as i32
+                            * INPUT_CHANS as i32
+                            * input_zero_point
+                            * filters_zero_point,
+                    );
+                    // Combine the constant values and the variants to obtain the output
+                    let y = constants
+                        .1
+                        .get(b)
+                        .copied()
+                        .unwrap_or(constants.1[0])
+                        .requantize(
+                            x.0 - x.1 - constants.2 + constants.3,
+                            constants.0[b],
+                            output_zero_point[0],
+                        );
+                    // Apply the fused activation function (if any), saturating the output
+                    activate(y, &options.fused_activation, output_zero_point[0])
+                });
             }
-        })
-    })];
-    Tensor4D::new(output, output_scale, output_zero_point)
+        }
+    }
+    output.scale = output_scale;
+    output.zero_point = output_zero_point;
 }
 
 #[cfg(test)]
@@ -147,10 +164,11 @@ mod tests {
     };
     const OUTPUT_SCALE: [f32; 1] = [0.49];
     const OUTPUT_ZERO_POINT: [i8; 1] = [50];
-    const OPTIONS: Conv2DOptions = Conv2DOptions {
+    const OPTIONS: Conv2DOptions<i8> = Conv2DOptions {
         fused_activation: FusedActivation::None,
         view_padding: TensorViewPadding::Same,
         strides: (1, 1),
+        dilations: (1, 1),
     };
     const CONSTANTS: (Buffer2D<f32, 2, 1>, Buffer2D<f32, 2, 1>) = (
         matrix![-3.673_469_4; -3.755_102],
@@ -167,16 +185,16 @@ mod tests {
 
     #[test]
     fn conv_2d_layer() {
-        assert_eq!(
-            conv_2d(
-                INPUT,
-                &FILTERS,
-                OUTPUT_SCALE,
-                OUTPUT_ZERO_POINT,
-                OPTIONS,
-                CONSTANTS,
-            ),
-            OUTPUT
+        let mut output = Tensor4D::default();
+        conv_2d(
+            &INPUT,
+            &FILTERS,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS,
+            &mut output,
         );
+        assert_eq!(output, OUTPUT);
     }
 }
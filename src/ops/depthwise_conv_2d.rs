@@ -1,21 +1,21 @@
 use core::array;
-use libm::roundf;
 
 use simba::scalar::SupersetOf;
 
-use crate::activation::{relu, relu6, FusedActivation};
+use crate::activation::{activate, FusedActivation};
 use crate::buffer::Buffer2D;
-use crate::quantize::Quantized;
+use crate::quantize::{Quantized, Requantizer};
 use crate::tensor::{Tensor4D, TensorView, TensorViewPadding};
 
-pub struct DepthwiseConv2DOptions {
-    pub fused_activation: FusedActivation,
+pub struct DepthwiseConv2DOptions<T: 'static> {
+    pub fused_activation: FusedActivation<T>,
     pub view_padding: TensorViewPadding,
     pub strides: (usize, usize),
+    pub dilations: (usize, usize),
 }
 
 /// Performs the DepthwiseConv2D operation.
-/// Returns a 4-dimensional output tensor containing the result of the operation.
+/// Writes the result of the operation into the 4-dimensional output tensor.
 ///
 /// # Arguments
 /// * `input` - The 4-dimensional input tensor
@@ -24,9 +24,12 @@ pub struct DepthwiseConv2DOptions {
 /// * `output_zero_point` - The zero point of the resulting output tensor
 /// * `options` - Operator's options as an [`DepthwiseConv2DOptions`] struct
 /// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The 4-dimensional output tensor
 ///
 pub fn depthwise_conv_2d<
     T: Quantized,
+    R: Requantizer,
+    const BATCHES: usize,
     const INPUT_ROWS: usize,
     const INPUT_COLS: usize,
     const INPUT_CHANS: usize,
@@ -37,71 +40,83 @@ pub fn depthwise_conv_2d<
     const OUTPUT_ROWS: usize,
     const OUTPUT_COLS: usize,
 >(
-    input: Tensor4D<T, 1, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
+    input: &Tensor4D<T, BATCHES, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
     weights: &Tensor4D<T, 1, WEIGHTS_ROWS, WEIGHTS_COLS, WEIGHTS_CHANS, WEIGHTS_QUANTS>,
     output_scale: [f32; 1],
     output_zero_point: [T; 1],
-    options: DepthwiseConv2DOptions,
+    options: DepthwiseConv2DOptions<T>,
     constants: (
-        Buffer2D<f32, WEIGHTS_CHANS, 1>,
-        Buffer2D<f32, WEIGHTS_QUANTS, 1>,
+        Buffer2D<R::Bias, WEIGHTS_CHANS, 1>,
+        Buffer2D<R, WEIGHTS_QUANTS, 1>,
     ),
-) -> Tensor4D<T, 1, OUTPUT_ROWS, OUTPUT_COLS, WEIGHTS_CHANS, 1> {
-    let output = [Buffer2D::from_fn(|i, j| {
-        // Extract the view using the view extraction algorithm
-        let view: TensorView<T, WEIGHTS_ROWS, WEIGHTS_COLS, INPUT_CHANS> =
-            input.view((i, j), 0, options.view_padding, options.strides);
-        // Perform the convolution for each input channel
-        array::from_fn(|c| {
-            let input_zero_point = i32::from_subset(&input.zero_point[0]);
-            let weights_zero_point = i32::from_subset(
-                &weights
-                    .zero_point
-                    .get(c)
-                    .copied()
-                    .unwrap_or(weights.zero_point[0]),
-            );
-            let x = (
-                // Perform the dot product between the input region and the weights
-                view.buffer.zip_fold(&weights.buffer[0], 0i32, |acc, v, w| {
-                    acc + i32::from_subset(&v.get(c).copied().unwrap_or(v[0]))
-                        * i32::from_subset(&w[c])
-                }),
-                // Perform the 2-dimensional component-sum of the view for the given channel
-                view.buffer.fold(0i32, |acc, a| {
-                    acc + i32::from_subset(&a.get(c).copied().unwrap_or(a[0]))
-                }) * weights_zero_point,
-            );
-            // Elaborate the constants
-            let constants = (
-                constants.0,
-                constants.1,
-                input_zero_point
-                    * weights.buffer[0].zip_fold(&view.mask, 0i32, |acc, w, m| {
-                        if m {
-                            acc + i32::from_subset(&w[c])
-                        } else {
-                            acc
-                        }
-                    }),
-                view.len as i32 * input_zero_point * weights_zero_point,
-            );
-            // Combine the constant values and the variants to obtain the output
-            let y = T::from_superset_unchecked(&roundf(
-                f32::from_subset(&output_zero_point[0])
-                    + constants.0[c]
-                    + constants.1.get(c).copied().unwrap_or(constants.1[0])
-                        * f32::from_subset(&(x.0 - x.1 - constants.2 + constants.3)),
-            ));
-            // Apply the fused activation function (if any)
-            match options.fused_activation {
-                FusedActivation::None => y,
-                FusedActivation::Relu => relu(y, output_zero_point[0]),
-                FusedActivation::Relu6 => relu6(y, output_scale[0], output_zero_point[0]),
+    output: &mut Tensor4D<T, BATCHES, OUTPUT_ROWS, OUTPUT_COLS, WEIGHTS_CHANS, 1>,
+) {
+    for batch in 0..BATCHES {
+        for i in 0..OUTPUT_ROWS {
+            for j in 0..OUTPUT_COLS {
+                // Extract the view using the view extraction algorithm
+                let view: TensorView<T, WEIGHTS_ROWS, WEIGHTS_COLS, INPUT_CHANS> = input.view(
+                    (i, j),
+                    batch,
+                    options.view_padding,
+                    options.strides,
+                    options.dilations,
+                );
+                // Perform the convolution for each output channel, i.e., for each input channel
+                // expanded by the depth multiplier
+                output.buffer[batch][(i, j)] = array::from_fn(|c| {
+                    let input_channel = c / (WEIGHTS_CHANS / INPUT_CHANS);
+                    let input_zero_point = i32::from_subset(&input.zero_point[0]);
+                    let weights_zero_point = i32::from_subset(
+                        &weights
+                            .zero_point
+                            .get(c)
+                            .copied()
+                            .unwrap_or(weights.zero_point[0]),
+                    );
+                    let x = (
+                        // Perform the dot product between the input region and the weights
+                        view.buffer.zip_fold(&weights.buffer[0], 0i32, |acc, v, w| {
+                            acc + i32::from_subset(&v[input_channel]) * i32::from_subset(&w[c])
+                        }),
+                        // Perform the 2-dimensional component-sum of the view for the given channel
+                        view.buffer
+                            .fold(0i32, |acc, a| acc + i32::from_subset(&a[input_channel]))
+                            * weights_zero_point,
+                    );
+                    // Elaborate the constants
+                    let constants = (
+                        constants.0,
+                        constants.1,
+                        input_zero_point
+                            * weights.buffer[0].zip_fold(&view.mask, 0i32, |acc, w, m| {
+                                if m {
+                                    acc + i32::from_subset(&w[c])
+                                } else {
+                                    acc
+                                }
+                            }),
+                        view.len as i32 * input_zero_point * weights_zero_point,
+                    );
+                    // Combine the constant values and the variants to obtain the output
+                    let y = constants
+                        .1
+                        .get(c)
+                        .copied()
+                        .unwrap_or(constants.1[0])
+                        .requantize(
+                            x.0 - x.1 - constants.2 + constants.3,
+                            constants.0[c],
+                            output_zero_point[0],
+                        );
+                    // Apply the fused activation function (if any), saturating the output
+                    activate(y, &options.fused_activation, output_zero_point[0])
+                });
             }
-        })
-    })];
-    Tensor4D::new(output, output_scale, output_zero_point)
+        }
+    }
+    output.scale = output_scale;
+    output.zero_point = output_zero_point;
 }
 
 #[cfg(test)]
@@ -138,10 +153,11 @@ mod tests {
     };
     const OUTPUT_SCALE: [f32; 1] = [0.37];
     const OUTPUT_ZERO_POINT: [i8; 1] = [38];
-    const OPTIONS: DepthwiseConv2DOptions = DepthwiseConv2DOptions {
+    const OPTIONS: DepthwiseConv2DOptions<i8> = DepthwiseConv2DOptions {
         fused_activation: FusedActivation::None,
         view_padding: TensorViewPadding::Same,
         strides: (1, 1),
+        dilations: (1, 1),
     };
     const CONSTANTS: (Buffer2D<f32, 2, 1>, Buffer2D<f32, 2, 1>) = (
         matrix![-3.567_567_6; -3.675_675_7],
@@ -158,16 +174,49 @@ mod tests {
 
     #[test]
     fn depthwise_conv_2d_layer() {
-        assert_eq!(
-            depthwise_conv_2d(
-                INPUT,
-                &WEIGHTS,
-                OUTPUT_SCALE,
-                OUTPUT_ZERO_POINT,
-                OPTIONS,
-                CONSTANTS,
-            ),
-            OUTPUT
+        let mut output = Tensor4D::default();
+        depthwise_conv_2d(
+            &INPUT,
+            &WEIGHTS,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT);
+    }
+
+    #[test]
+    fn depthwise_conv_2d_layer_depth_multiplier() {
+        let weights: Tensor4D<i8, 1, 1, 1, 4, 1> = Tensor4D {
+            buffer: [matrix![[1, 2, 3, 4]]],
+            scale: [0.5],
+            zero_point: [0],
+        };
+        let options = DepthwiseConv2DOptions {
+            view_padding: TensorViewPadding::Valid,
+            ..OPTIONS
+        };
+        let constants = (matrix![0.; 0.; 0.; 0.], matrix![0.25]);
+        let expected: Tensor4D<i8, 1, 2, 3, 4, 1> = Tensor4D {
+            buffer: [matrix![
+                [35, 32, 29, 26], [35, 33, 31, 28], [36, 34, 32, 30];
+                [36, 35, 34, 32], [37, 36, 35, 34], [37, 37, 37, 36]
+            ]],
+            scale: OUTPUT_SCALE,
+            zero_point: OUTPUT_ZERO_POINT,
+        };
+        let mut output = Tensor4D::default();
+        depthwise_conv_2d(
+            &INPUT,
+            &weights,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            options,
+            constants,
+            &mut output,
         );
+        assert_eq!(output, expected);
     }
 }
@@ -1,84 +1,90 @@
-use libm::roundf;
 use simba::scalar::SupersetOf;
 
-use crate::activation::{relu, relu6, FusedActivation};
+use crate::activation::{activate, FusedActivation};
 use crate::buffer::Buffer2D;
-use crate::quantize::Quantized;
-use crate::tensor::Tensor2D;
+use crate::quantize::{Quantized, Requantizer};
+use crate::tensor::{Tensor2D, TensorFlatten};
 
-pub struct FullyConnectedOptions {
-    pub fused_activation: FusedActivation,
+pub struct FullyConnectedOptions<T: 'static> {
+    pub fused_activation: FusedActivation<T>,
 }
 
 /// Performs the FullyConnected operation.
-/// Returns a 2-dimensional output tensor containing the result of the operation.
+/// Writes the result of the operation into the 2-dimensional output tensor.
 ///
 /// # Arguments
-/// * `input` - The 2-dimensional input tensor
+/// * `input` - The input tensor, flattened to 2 dimensions
 /// * `weights` - The 2-dimensional tensor representing the weights of the operator
 /// * `output_scale` - The scale of the resulting output tensor
 /// * `output_zero_point` - The zero point of the resulting output tensor
 /// * `options` - Operator's options as an [`FullyConnectedOptions`] struct
 /// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The 2-dimensional output tensor
 ///
 pub fn fully_connected<
     T: Quantized,
+    R: Requantizer,
     const INPUT_ROWS: usize,
     const INPUT_COLS: usize,
     const WEIGHTS_COLS: usize,
+    const WEIGHTS_QUANTS: usize,
+    InputT,
 >(
-    input: Tensor2D<T, INPUT_ROWS, INPUT_COLS, 1>,
-    weights: &Tensor2D<T, INPUT_COLS, WEIGHTS_COLS, 1>,
+    input: &InputT,
+    weights: &Tensor2D<T, INPUT_COLS, WEIGHTS_COLS, WEIGHTS_QUANTS>,
     output_scale: [f32; 1],
     output_zero_point: [T; 1],
-    options: FullyConnectedOptions,
+    options: FullyConnectedOptions<T>,
     constants: (
-        Buffer2D<f32, WEIGHTS_COLS, 1>,
-        f32,
+        Buffer2D<R::Bias, WEIGHTS_COLS, 1>,
+        Buffer2D<R, WEIGHTS_QUANTS, 1>,
         Buffer2D<i32, 1, WEIGHTS_COLS>,
         i32,
     ),
-) -> Tensor2D<T, INPUT_ROWS, WEIGHTS_COLS, 1> {
-    let x: (
-        Buffer2D<i32, INPUT_ROWS, WEIGHTS_COLS>,
-        Buffer2D<i32, INPUT_ROWS, 1>,
-    ) = (
-        // Perform the dot product between the input and the weights
-        Buffer2D::from_fn(|i, j| {
-            input
+    output: &mut Tensor2D<T, INPUT_ROWS, WEIGHTS_COLS, 1>,
+) where
+    InputT: TensorFlatten<T, INPUT_ROWS, INPUT_COLS>,
+{
+    for i in 0..INPUT_ROWS {
+        // Perform the row-sum of the input
+        let row_sum = (0..INPUT_COLS).fold(0i32, |acc, k| {
+            acc + i32::from_subset(&input.get_flat((i, k)))
+        });
+        for j in 0..WEIGHTS_COLS {
+            // Perform the dot product between the input and the weights
+            let dot = weights
                 .buffer
-                .row(i)
+                .column(j)
                 .iter()
-                .zip(weights.buffer.column(j).iter())
-                .fold(0i32, |acc, (i, w)| {
-                    acc + i32::from_subset(i) * i32::from_subset(w)
-                })
-        }),
-        // Perform the row-sum of the weights
-        Buffer2D::from_fn(|i, _| {
-            input
-                .buffer
-                .row(i)
-                .fold(0i32, |acc, e| acc + i32::from_subset(&e))
-                * i32::from_subset(&weights.zero_point[0])
-        }),
-    );
-    // Combine the constant values and the variants to obtain the output
-    let output = Buffer2D::from_fn(|i, j| {
-        let y = T::from_superset_unchecked(&roundf(
-            f32::from_subset(&output_zero_point[0])
-                + constants.0[j]
-                + constants.1
-                    * f32::from_subset(&(x.0[(i, j)] - x.1[i] - constants.2[j] + constants.3)),
-        ));
-        // Apply the fused activation function (if any)
-        match options.fused_activation {
-            FusedActivation::None => y,
-            FusedActivation::Relu => relu(y, output_zero_point[0]),
-            FusedActivation::Relu6 => relu6(y, output_scale[0], output_zero_point[0]),
+                .enumerate()
+                .fold(0i32, |acc, (k, w)| {
+                    acc + i32::from_subset(&input.get_flat((i, k))) * i32::from_subset(w)
+                });
+            // Combine the constant values and the variants to obtain the output
+            // Per-channel weights have a zero point and a multiplier for each output channel
+            let weights_zero_point = i32::from_subset(
+                &weights
+                    .zero_point
+                    .get(j)
+                    .copied()
+                    .unwrap_or(weights.zero_point[0]),
+            );
+            let y = constants
+                .1
+                .get(j)
+                .copied()
+                .unwrap_or(constants.1[0])
+                .requantize(
+                    dot - (row_sum - constants.3) * weights_zero_point - constants.2[j],
+                    constants.0[j],
+                    output_zero_point[0],
+                );
+            // Apply the fused activation function (if any), saturating the output
+            output.buffer[(i, j)] = activate(y, &options.fused_activation, output_zero_point[0]);
         }
-    });
-    Tensor2D::new(output, output_scale, output_zero_point)
+    }
+    output.scale = output_scale;
+    output.zero_point = output_zero_point;
 }
 
 #[cfg(test)]
@@ -86,6 +92,7 @@ mod tests {
     use nalgebra::matrix;
 
     use super::*;
+    use crate::tensor::Tensor4D;
 
     const INPUT: Tensor2D<i8, 2, 3, 1> = Tensor2D {
         buffer: matrix![
@@ -113,14 +120,19 @@ mod tests {
     };
     const OUTPUT_SCALE: [f32; 1] = [0.29];
     const OUTPUT_ZERO_POINT: [i8; 1] = [30];
-    const OPTIONS: FullyConnectedOptions = FullyConnectedOptions {
+    const OPTIONS: FullyConnectedOptions<i8> = FullyConnectedOptions {
         fused_activation: FusedActivation::Relu,
     };
-    const CONSTANTS: (Buffer2D<f32, 4, 1>, f32, Buffer2D<i32, 1, 4>, i32) = (
+    const CONSTANTS: (
+        Buffer2D<f32, 4, 1>,
+        Buffer2D<f32, 1, 1>,
+        Buffer2D<i32, 1, 4>,
+        i32,
+    ) = (
         matrix![-4.655_172_3; -3.724_138; -2.793_103_5; -1.862_069],
-        0.506_896_56,
+        matrix![0.506_896_56],
         matrix![312, 336, 360, 384],
-        528,
+        24,
     );
     const OUTPUT: Tensor2D<i8, 2, 4, 1> = Tensor2D {
         buffer: matrix![
@@ -131,18 +143,79 @@ mod tests {
         zero_point: [30],
     };
 
+    const WEIGHTS_PER_CHANNEL: Tensor2D<i8, 3, 4, 4> = Tensor2D {
+        buffer: WEIGHTS.buffer,
+        scale: [0.21, 0.22, 0.23, 0.24],
+        zero_point: [22, 0, 22, 0],
+    };
+    const CONSTANTS_PER_CHANNEL: (
+        Buffer2D<f32, 4, 1>,
+        Buffer2D<f32, 4, 1>,
+        Buffer2D<i32, 1, 4>,
+        i32,
+    ) = (
+        CONSTANTS.0,
+        matrix![0.5; 0.25; 0.5; 0.25],
+        CONSTANTS.2,
+        CONSTANTS.3,
+    );
+    const OUTPUT_PER_CHANNEL: Tensor2D<i8, 2, 4, 1> = Tensor2D {
+        buffer: matrix![
+            110, 30, 94, 30;
+            70,  30, 63, 30
+        ],
+        scale: [0.29],
+        zero_point: [30],
+    };
+
     #[test]
     fn fully_connected_layer() {
-        assert_eq!(
-            fully_connected(
-                INPUT,
-                &WEIGHTS,
-                OUTPUT_SCALE,
-                OUTPUT_ZERO_POINT,
-                OPTIONS,
-                CONSTANTS
-            ),
-            OUTPUT
-        )
+        let mut output = Tensor2D::default();
+        fully_connected(
+            &INPUT,
+            &WEIGHTS,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT)
+    }
+
+    #[test]
+    fn fully_connected_layer_4d() {
+        // The input is flattened to the rows of the 2-dimensional input
+        let input: Tensor4D<i8, 2, 1, 3, 1, 1> = Tensor4D::new(
+            [matrix![[1], [2], [3]], matrix![[4], [5], [6]]],
+            INPUT.scale,
+            INPUT.zero_point,
+        );
+        let mut output = Tensor2D::default();
+        fully_connected(
+            &input,
+            &WEIGHTS,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT)
+    }
+
+    #[test]
+    fn fully_connected_layer_per_channel() {
+        let mut output = Tensor2D::default();
+        fully_connected(
+            &INPUT,
+            &WEIGHTS_PER_CHANNEL,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            CONSTANTS_PER_CHANNEL,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_PER_CHANNEL)
     }
 }
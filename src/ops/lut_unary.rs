@@ -0,0 +1,170 @@
+use crate::quantize::{look_up, Quantized};
+use crate::tensor::TensorMap;
+
+/// Performs an elementwise unary function (e.g., Logistic, Tanh, or Exp) as an operator.
+/// The results are looked up in a table computed by the compiler by evaluating the function on
+/// every quantized input, i.e., a table of 256 entries for 8-bit types and of 65536 for `i16`.
+/// Writes the result of the operation into the output tensor of the same shape.
+///
+/// # Arguments
+/// * `input` - The input tensor
+/// * `output_scale` - The scale of the resulting output tensor
+/// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The output tensor
+///
+pub fn lut_unary<T: Quantized, InputT, OutputT, const N: usize>(
+    input: &InputT,
+    output_scale: [f32; 1],
+    output_zero_point: [T; 1],
+    constants: &[T; N],
+    output: &mut OutputT,
+) where
+    InputT: TensorMap<T, T, OutputT>,
+{
+    input.map_into(
+        |x| look_up(x, constants),
+        output_scale,
+        output_zero_point,
+        output,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use core::array;
+
+    use nalgebra::matrix;
+
+    use crate::tensor::{Tensor2D, Tensor4D};
+
+    use super::*;
+
+    const INPUT_2D: Tensor2D<i8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            -128, -1, 0;
+            1,    42, 127
+        ],
+        scale: [0.05],
+        zero_point: [0],
+    };
+    const INPUT_4D: Tensor4D<i8, 1, 2, 3, 1, 1> = Tensor4D {
+        buffer: [matrix![
+            [-128], [-1], [0];
+            [1],    [42], [127]
+        ]],
+        scale: [0.05],
+        zero_point: [0],
+    };
+    const OUTPUT_SCALE: [f32; 1] = [0.003_906_25];
+    const OUTPUT_ZERO_POINT: [i8; 1] = [-128];
+    const OUTPUT_2D: Tensor2D<i8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            -128, -2, -1;
+            0,    41, 126
+        ],
+        scale: OUTPUT_SCALE,
+        zero_point: OUTPUT_ZERO_POINT,
+    };
+
+    #[test]
+    fn lut_unary_layer() {
+        // The table is indexed from the minimum value, i.e., it holds the input value minus one
+        let constants: [i8; 256] = array::from_fn(|i| (i as i32 - 129).max(-128) as i8);
+        let mut output = Tensor2D::default();
+        lut_unary(
+            &INPUT_2D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            &constants,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_2D);
+        let mut output = Tensor4D::default();
+        lut_unary(
+            &INPUT_4D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            &constants,
+            &mut output,
+        );
+        assert_eq!(
+            output.buffer,
+            [matrix![
+                [-128], [-2], [-1];
+                [0],    [41], [126]
+            ]]
+        );
+    }
+
+    #[test]
+    fn lut_unary_layer_logistic() {
+        // The table is computed as the compiler does, with an output scale of 1 / 256
+        let scale = 1. / 256.;
+        let constants: [i8; 256] = array::from_fn(|i| {
+            let x = (i as f32 - 128.) * INPUT_2D.scale[0];
+            (libm::roundf(1. / (1. + libm::expf(-x)) / scale) - 128.).clamp(-128., 127.) as i8
+        });
+        let mut output: Tensor2D<i8, 2, 3, 1> = Tensor2D::default();
+        lut_unary(&INPUT_2D, [scale], [-128], &constants, &mut output);
+        assert_eq!(
+            output.buffer,
+            matrix![
+                -128, -3,  0;
+                3,    100, 127
+            ]
+        );
+        // The results are within one quantization step of the sigmoid
+        for (x, y) in INPUT_2D.buffer.iter().zip(output.buffer.iter()) {
+            let expected = 1. / (1. + libm::expf(-(*x as f32) * INPUT_2D.scale[0]));
+            assert!(((*y as f32 + 128.) * scale - expected).abs() <= scale);
+        }
+    }
+
+    #[test]
+    fn lut_unary_layer_tanh() {
+        // The table is computed as the compiler does, with an output scale of 1 / 128
+        let scale = 1. / 128.;
+        let constants: [i8; 256] = array::from_fn(|i| {
+            let x = (i as f32 - 128.) * INPUT_2D.scale[0];
+            libm::roundf(libm::tanhf(x) / scale).clamp(-128., 127.) as i8
+        });
+        let mut output: Tensor2D<i8, 2, 3, 1> = Tensor2D::default();
+        lut_unary(&INPUT_2D, [scale], [0], &constants, &mut output);
+        assert_eq!(
+            output.buffer,
+            matrix![
+                -128, -6,  0;
+                6,    124, 127
+            ]
+        );
+        // The results are within one quantization step of the hyperbolic tangent
+        for (x, y) in INPUT_2D.buffer.iter().zip(output.buffer.iter()) {
+            let expected = libm::tanhf(*x as f32 * INPUT_2D.scale[0]);
+            assert!((*y as f32 * scale - expected).abs() <= scale);
+        }
+    }
+
+    #[test]
+    fn lut_unary_layer_i16() {
+        let input: Tensor2D<i16, 2, 3, 1> = Tensor2D {
+            buffer: matrix![
+                -32768, -1,   0;
+                1,      1000, 32767
+            ],
+            scale: [0.001],
+            zero_point: [0],
+        };
+        // The table of 65536 entries holds the input value halved
+        let constants: [i16; 65536] = array::from_fn(|i| ((i as i32 - 32768) / 2) as i16);
+        let mut output = Tensor2D::default();
+        lut_unary(&input, [0.002], [0], &constants, &mut output);
+        assert_eq!(
+            output.buffer,
+            matrix![
+                -16384, 0,   0;
+                0,      500, 16383
+            ]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use core::array;
+use core::cmp::max;
+
+use nalgebra::Const;
+use simba::scalar::SupersetOf;
+
+use crate::activation::{activate, FusedActivation};
+use crate::quantize::Quantized;
+use crate::tensor::{Tensor4D, TensorView, TensorViewPadding};
+
+pub struct MaxPool2DOptions<T: 'static> {
+    pub fused_activation: FusedActivation<T>,
+    pub view_padding: TensorViewPadding,
+    pub strides: (usize, usize),
+}
+
+/// Performs the MaxPool2D operation.
+/// Writes the result of the operation into the 4-dimensional output tensor.
+///
+/// # Arguments
+/// * `input` - The 4-dimensional input tensor
+/// * `_filter_shape` - The phantom shape of the filter
+/// * `output_scale` - The scale of the resulting output tensor
+/// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `options` - Operator's options as an [`MaxPool2DOptions`] struct
+/// * `output` - The 4-dimensional output tensor
+///
+pub fn max_pool_2d<
+    T: Quantized,
+    const BATCHES: usize,
+    const INPUT_ROWS: usize,
+    const INPUT_COLS: usize,
+    const INPUT_CHANS: usize,
+    const FILTER_ROWS: usize,
+    const FILTER_COLS: usize,
+    const OUTPUT_ROWS: usize,
+    const OUTPUT_COLS: usize,
+>(
+    input: &Tensor4D<T, BATCHES, INPUT_ROWS, INPUT_COLS, INPUT_CHANS, 1>,
+    _filter_shape: (Const<FILTER_ROWS>, Const<FILTER_COLS>),
+    output_scale: [f32; 1],
+    output_zero_point: [T; 1],
+    options: MaxPool2DOptions<T>,
+    output: &mut Tensor4D<T, BATCHES, OUTPUT_ROWS, OUTPUT_COLS, INPUT_CHANS, 1>,
+) {
+    for batch in 0..BATCHES {
+        for i in 0..OUTPUT_ROWS {
+            for j in 0..OUTPUT_COLS {
+                // Extract the view using the view extraction algorithm
+                let view: TensorView<T, FILTER_ROWS, FILTER_COLS, INPUT_CHANS> =
+                    input.view((i, j), batch, options.view_padding, options.strides, (1, 1));
+                // Compute the max pooling for each channel, skipping the padded values
+                output.buffer[batch][(i, j)] = array::from_fn(|c| {
+                    let y = view
+                        .buffer
+                        .zip_fold(&view.mask, None, |acc, a, m| match (acc, m) {
+                            (_, false) => acc,
+                            (None, true) => Some(a[c]),
+                            (Some(x), true) => Some(max(x, a[c])),
+                        })
+                        .unwrap_or(output_zero_point[0]);
+                    // Apply the fused activation function (if any)
+                    activate(
+                        i32::from_subset(&y),
+                        &options.fused_activation,
+                        output_zero_point[0],
+                    )
+                });
+            }
+        }
+    }
+    output.scale = output_scale;
+    output.zero_point = output_zero_point;
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::matrix;
+
+    use super::*;
+
+    const INPUT: Tensor4D<i8, 1, 2, 3, 2, 1> = Tensor4D {
+        buffer: [matrix![
+            [-5, 2],  [3,  -4], [-6, 6];
+            [7,  -8], [-9, 10], [11, -12]
+        ]],
+        scale: [0.13],
+        zero_point: [10],
+    };
+    const FILTER_SHAPE: (Const<2>, Const<3>) = (Const, Const);
+    const OUTPUT_SCALE: [f32; 1] = [0.13];
+    const OUTPUT_ZERO_POINT: [i8; 1] = [10];
+    const OPTIONS: MaxPool2DOptions<i8> = MaxPool2DOptions {
+        fused_activation: FusedActivation::Relu,
+        view_padding: TensorViewPadding::Same,
+        strides: (1, 1),
+    };
+    const OUTPUT: Tensor4D<i8, 1, 2, 3, 2, 1> = Tensor4D {
+        buffer: [matrix![
+            [10, 10], [11, 10], [11, 10];
+            [10, 10], [11, 10], [11, 10]
+        ]],
+        scale: [0.13],
+        zero_point: [10],
+    };
+
+    #[test]
+    fn max_pool_2d_layer() {
+        let mut output = Tensor4D::default();
+        max_pool_2d(
+            &INPUT,
+            FILTER_SHAPE,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            OPTIONS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT);
+    }
+}
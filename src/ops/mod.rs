@@ -1,13 +1,21 @@
+mod add;
 mod average_pool_2d;
 mod conv_2d;
 mod depthwise_conv_2d;
 mod fully_connected;
+mod lut_unary;
+mod max_pool_2d;
+mod quantize;
 mod reshape;
 mod softmax;
 
+pub use add::*;
 pub use average_pool_2d::*;
 pub use conv_2d::*;
 pub use depthwise_conv_2d::*;
 pub use fully_connected::*;
+pub use lut_unary::*;
+pub use max_pool_2d::*;
+pub use quantize::*;
 pub use reshape::*;
 pub use softmax::*;
@@ -0,0 +1,125 @@
+use simba::scalar::SupersetOf;
+
+use crate::quantize::{saturate, Quantized, Requantizer};
+use crate::tensor::TensorMap;
+
+/// Performs the Quantize operation.
+/// Writes into the output tensor the input elements requantized to the output quantization,
+/// possibly of a different type (e.g., from `i8` to `u8`).
+///
+/// # Arguments
+/// * `input` - The input tensor
+/// * `output_scale` - The scale of the resulting output tensor
+/// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The output tensor
+///
+pub fn quantize<T1: Quantized, T2: Quantized, R: Requantizer, InputT, OutputT>(
+    input: &InputT,
+    output_scale: [f32; 1],
+    output_zero_point: [T2; 1],
+    constants: (R, R::Bias),
+    output: &mut OutputT,
+) where
+    InputT: TensorMap<T1, T2, OutputT>,
+{
+    input.map_into(
+        |x| {
+            saturate(constants.0.requantize(
+                i32::from_subset(&x),
+                constants.1,
+                output_zero_point[0],
+            ))
+        },
+        output_scale,
+        output_zero_point,
+        output,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::matrix;
+
+    use crate::quantize::QuantizedMultiplier;
+    use crate::tensor::{Tensor2D, Tensor4D};
+
+    use super::*;
+
+    const INPUT_2D: Tensor2D<i8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            -128, -1, 0;
+            1,    42, 127
+        ],
+        scale: [0.5],
+        zero_point: [-3],
+    };
+    const INPUT_4D: Tensor4D<i8, 1, 2, 3, 1, 1> = Tensor4D {
+        buffer: [matrix![
+            [-128], [-1], [0];
+            [1],    [42], [127]
+        ]],
+        scale: [0.5],
+        zero_point: [-3],
+    };
+    const OUTPUT_SCALE: [f32; 1] = [0.5];
+    const OUTPUT_ZERO_POINT: [u8; 1] = [125];
+    const CONSTANTS: (f32, f32) = (1., 3.);
+    const CONSTANTS_INTEGER: (QuantizedMultiplier, i32) =
+        (QuantizedMultiplier::new(1073741824, 1), 3);
+    const OUTPUT_2D: Tensor2D<u8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            0,   127, 128;
+            129, 170, 255
+        ],
+        scale: [0.5],
+        zero_point: [125],
+    };
+    const OUTPUT_4D: Tensor4D<u8, 1, 2, 3, 1, 1> = Tensor4D {
+        buffer: [matrix![
+            [0],   [127], [128];
+            [129], [170], [255]
+        ]],
+        scale: [0.5],
+        zero_point: [125],
+    };
+
+    #[test]
+    fn quantize_2d_layer() {
+        let mut output: Tensor2D<u8, 2, 3, 1> = Tensor2D::default();
+        quantize(
+            &INPUT_2D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_2D);
+    }
+
+    #[test]
+    fn quantize_4d_layer() {
+        let mut output: Tensor4D<u8, 1, 2, 3, 1, 1> = Tensor4D::default();
+        quantize(
+            &INPUT_4D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_4D);
+    }
+
+    #[test]
+    fn quantize_2d_layer_integer() {
+        let mut output: Tensor2D<u8, 2, 3, 1> = Tensor2D::default();
+        quantize(
+            &INPUT_2D,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            CONSTANTS_INTEGER,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_2D);
+    }
+}
@@ -1,10 +1,17 @@
+use crate::tensor::TensorReshape;
+
 /// Performs the Reshape operator.
-/// Returns the correspondig output tensor.
-pub fn reshape<InputT, OutputT>(input: InputT) -> OutputT
+/// Writes the input elements into the output tensor of the new shape.
+///
+/// # Arguments
+/// * `input` - The input tensor
+/// * `output` - The output tensor
+///
+pub fn reshape<InputT, OutputT>(input: &InputT, output: &mut OutputT)
 where
-    InputT: Into<OutputT>,
+    InputT: TensorReshape<OutputT>,
 {
-    input.into()
+    input.reshape_into(output)
 }
 
 #[cfg(test)]
@@ -29,7 +36,8 @@ mod tests {
 
     #[test]
     fn reshape_layer() {
-        let output: Tensor4D<i8, 2, 1, 3, 1, 1> = reshape(INPUT);
+        let mut output: Tensor4D<i8, 2, 1, 3, 1, 1> = Tensor4D::default();
+        reshape(&INPUT, &mut output);
         assert_eq!(output, OUTPUT);
     }
 }
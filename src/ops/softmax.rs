@@ -1,34 +1,153 @@
+use core::array;
+use core::mem::size_of;
+
 use crate::activation;
-use crate::quantize::Quantized;
-use crate::tensor::Tensor2D;
+use crate::quantize::{
+    clamp, exp_on_negative_values, multiply_by_quantized_multiplier, reciprocal,
+    rounding_divide_by_pot, saturating_rounding_doubling_high_mul, Quantized, QuantizedMultiplier,
+};
+use crate::tensor::TensorMapLastAxis;
 use libm::expf;
 use simba::scalar::SupersetOf;
 
+/// The number of integer bits of the accumulator of the exponentials in [`softmax_integer`].
+const ACCUMULATION_INTEGER_BITS: i32 = 12;
+
 /// Performs the Softmax activation function as an operator.
-/// Returns a 2-dimensional output tensor containing the result of the operation.
+/// The function is applied along the last axis of the input, i.e., to each row of a `Tensor2D`
+/// and to each channel vector of a `Tensor4D`.
+/// Writes the result of the operation into the output tensor of the same shape.
+///
+/// # Arguments
+/// * `input` - The input tensor
+/// * `output_scale` - The scale of the resulting output tensor
+/// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The output tensor
+///
+pub fn softmax<T: Quantized, const LEN: usize, InputT>(
+    input: &InputT,
+    output_scale: [f32; 1],
+    output_zero_point: [T; 1],
+    constants: f32,
+    output: &mut InputT,
+) where
+    InputT: TensorMapLastAxis<T, LEN>,
+{
+    input.map_last_axis_into(
+        |x| {
+            // The inputs are rescaled by the input scale and by the inverse temperature
+            let exp = x.map(|e| f32::from_subset(&e) * constants);
+            let sum: f32 = exp.iter().map(|&e| expf(e)).sum();
+            exp.map(|e| activation::softmax(e, sum, output_scale[0], output_zero_point[0]))
+        },
+        output_scale,
+        output_zero_point,
+        output,
+    )
+}
+
+/// Performs the Softmax activation function as an operator, with a lookup table of the
+/// exponentials and integer-only normalization.
+/// The function is applied along the last axis of the input, and the output is expected to be
+/// quantized with a scale of `1 / 256`.
+/// Writes the result of the operation into the output tensor of the same shape.
+///
+/// # Arguments
+/// * `input` - The input tensor
+/// * `output_scale` - The scale of the resulting output tensor
+/// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The output tensor
+///
+pub fn softmax_lut<T: Quantized, const LEN: usize, InputT>(
+    input: &InputT,
+    output_scale: [f32; 1],
+    output_zero_point: [T; 1],
+    constants: &[u32; 256],
+    output: &mut InputT,
+) where
+    InputT: TensorMapLastAxis<T, LEN>,
+{
+    input.map_last_axis_into(
+        |x| {
+            let max = i32::from_subset(&x.iter().copied().max().unwrap());
+            // Look up the exponential of the difference from the maximum in Q16 format
+            let exp = |e: T| constants[(max - i32::from_subset(&e)) as usize];
+            let sum: u32 = x.iter().map(|&e| exp(e)).sum();
+            x.map(|e| {
+                // Divide by the sum with rounding, rescaling the probability to the output scale
+                let y = ((exp(e) << 8) + sum / 2) / sum;
+                // The probability of 1 is not representable, so the saturation is not counted
+                T::from_superset_unchecked(&clamp::<T>(
+                    y as i32 + i32::from_subset(&output_zero_point[0]),
+                ))
+            })
+        },
+        output_scale,
+        output_zero_point,
+        output,
+    )
+}
+
+/// Performs the Softmax activation function as an operator, with integer-only arithmetic.
+/// The function is applied along the last axis of the input, and the output is expected to be
+/// quantized with a scale of `1 / 256` and the minimum value of `T` as zero point.
+/// Writes the result of the operation into the output tensor of the same shape.
 ///
 /// # Arguments
-/// * `input` - The 2-dimensional input tensor
+/// * `input` - The input tensor
 /// * `output_scale` - The scale of the resulting output tensor
 /// * `output_zero_point` - The zero point of the resulting output tensor
+/// * `constants` - Constant values coming from the pre-processing phase
+/// * `output` - The output tensor
 ///
-pub fn softmax<T: Quantized, const ROWS: usize, const COLS: usize>(
-    input: Tensor2D<T, ROWS, COLS, 1>,
+pub fn softmax_integer<T: Quantized, const LEN: usize, InputT>(
+    input: &InputT,
     output_scale: [f32; 1],
     output_zero_point: [T; 1],
-) -> Tensor2D<T, ROWS, COLS, 1> {
-    let exp = input.buffer.map(|e| f32::from_subset(&e) * input.scale[0]);
-    let sum = exp.map(expf).sum();
-    Tensor2D::new(
-        exp.map(|e| activation::softmax(e, sum, output_scale[0], output_zero_point[0])),
+    constants: (QuantizedMultiplier, i32),
+    output: &mut InputT,
+) where
+    InputT: TensorMapLastAxis<T, LEN>,
+{
+    // Compute the exponential of the difference from the maximum in Q0.31 format, discarding the
+    // differences whose exponential is negligible
+    let exp = |x: T, max: T| {
+        let diff = i32::from_subset(&x) - i32::from_subset(&max);
+        (diff >= constants.1)
+            .then(|| exp_on_negative_values(multiply_by_quantized_multiplier(diff, constants.0)))
+    };
+    let output_bits = 8 * size_of::<T>() as i32;
+    input.map_last_axis_into(
+        |x| {
+            let max = x.iter().copied().max().unwrap();
+            let sum = x.iter().filter_map(|&e| exp(e, max)).fold(0, |acc, e| {
+                acc + rounding_divide_by_pot(e, ACCUMULATION_INTEGER_BITS)
+            });
+            let (reciprocal, exponent) = reciprocal(sum, ACCUMULATION_INTEGER_BITS);
+            array::from_fn(|i| match exp(x[i], max) {
+                Some(e) => {
+                    let y = rounding_divide_by_pot(
+                        saturating_rounding_doubling_high_mul(reciprocal, e),
+                        exponent + 31 - output_bits,
+                    );
+                    // The probability of 1 is not representable, so the saturation is not counted
+                    T::from_superset_unchecked(&clamp::<T>(y + i32::from_subset(&T::min_value())))
+                }
+                None => T::min_value(),
+            })
+        },
         output_scale,
         output_zero_point,
+        output,
     )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tensor::{Tensor2D, Tensor4D};
     use nalgebra::matrix;
 
     const INPUT: Tensor2D<i8, 2, 3, 1> = Tensor2D {
@@ -39,19 +158,114 @@ mod tests {
         scale: [0.7],
         zero_point: [8],
     };
+    const INPUT_4D: Tensor4D<i8, 1, 1, 2, 3, 1> = Tensor4D {
+        buffer: [matrix![[1, 2, 3], [4, 5, 6]]],
+        scale: [0.7],
+        zero_point: [8],
+    };
     const OUTPUT_SCALE: [f32; 1] = [0.9];
     const OUTPUT_ZERO_POINT: [i8; 1] = [10];
+    // The input scale (with a beta of 1)
+    const CONSTANTS: f32 = 0.7;
     const OUTPUT: Tensor2D<i8, 2, 3, 1> = Tensor2D {
         buffer: matrix![
-            10, 10, 10;
+            10, 10, 11;
             10, 10, 11
         ],
         scale: OUTPUT_SCALE,
         zero_point: OUTPUT_ZERO_POINT,
     };
 
+    const OUTPUT_SCALE_INTEGER: [f32; 1] = [0.003_906_25];
+    const OUTPUT_ZERO_POINT_INTEGER: [i8; 1] = [-128];
+    // The input scale in Q5.26 format (0.7 * 2^26), discarding the differences below -31
+    const CONSTANTS_INTEGER: (QuantizedMultiplier, i32) =
+        (QuantizedMultiplier::new(1503238528, 26), -31);
+    const OUTPUT_INTEGER: Tensor2D<i8, 2, 3, 1> = Tensor2D {
+        buffer: matrix![
+            -92, -55, 19;
+            -92, -55, 19
+        ],
+        scale: OUTPUT_SCALE_INTEGER,
+        zero_point: OUTPUT_ZERO_POINT_INTEGER,
+    };
+
     #[test]
     fn softmax_layer() {
-        assert_eq!(softmax(INPUT, OUTPUT_SCALE, OUTPUT_ZERO_POINT), OUTPUT);
+        let mut output = Tensor2D::default();
+        softmax(
+            &INPUT,
+            OUTPUT_SCALE,
+            OUTPUT_ZERO_POINT,
+            CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT);
+    }
+
+    #[test]
+    fn softmax_layer_4d_beta() {
+        // The input scale multiplied by a beta of 2, sharpening the distribution
+        let mut output = Tensor4D::default();
+        softmax(
+            &INPUT_4D,
+            [0.1],
+            OUTPUT_ZERO_POINT,
+            2. * CONSTANTS,
+            &mut output,
+        );
+        assert_eq!(output.buffer, [matrix![[10, 12, 18], [10, 12, 18]]]);
+        softmax(&INPUT_4D, [0.1], OUTPUT_ZERO_POINT, CONSTANTS, &mut output);
+        assert_eq!(output.buffer, [matrix![[11, 13, 16], [11, 13, 16]]]);
+    }
+
+    #[test]
+    fn softmax_integer_layer() {
+        let mut output = Tensor2D::default();
+        softmax_integer(
+            &INPUT,
+            OUTPUT_SCALE_INTEGER,
+            OUTPUT_ZERO_POINT_INTEGER,
+            CONSTANTS_INTEGER,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_INTEGER);
+    }
+
+    #[test]
+    fn softmax_integer_layer_4d() {
+        let mut output = Tensor4D::default();
+        softmax_integer(
+            &INPUT_4D,
+            OUTPUT_SCALE_INTEGER,
+            OUTPUT_ZERO_POINT_INTEGER,
+            CONSTANTS_INTEGER,
+            &mut output,
+        );
+        assert_eq!(output.buffer, [matrix![[-92, -55, 19], [-92, -55, 19]]]);
+    }
+
+    #[test]
+    fn softmax_lut_layer() {
+        // The exponentials of the differences scaled by 0.7, in Q16 format
+        let constants = array::from_fn(|d| libm::roundf(expf(-0.7 * d as f32) * 65536.) as u32);
+        let mut output = Tensor2D::default();
+        softmax_lut(
+            &INPUT,
+            OUTPUT_SCALE_INTEGER,
+            OUTPUT_ZERO_POINT_INTEGER,
+            &constants,
+            &mut output,
+        );
+        assert_eq!(output, OUTPUT_INTEGER);
+        let mut output = Tensor4D::default();
+        softmax_lut(
+            &INPUT_4D,
+            OUTPUT_SCALE_INTEGER,
+            OUTPUT_ZERO_POINT_INTEGER,
+            &constants,
+            &mut output,
+        );
+        assert_eq!(output.buffer, [matrix![[-92, -55, 19], [-92, -55, 19]]]);
     }
 }
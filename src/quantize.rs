@@ -1,12 +1,35 @@
+#[cfg(feature = "saturation-counter")]
+use core::sync::atomic::{AtomicUsize, Ordering};
 use libm::roundf;
 use nalgebra::Scalar;
+use num_traits::Bounded;
 use simba::scalar::{SubsetOf, SupersetOf};
 
 /// Represents the trait to constrain a type to be quantized.
-pub trait Quantized: Scalar + Copy + Ord + SubsetOf<i32> + SubsetOf<f32> {}
-impl<T: Scalar + Copy + Ord + SubsetOf<i32> + SubsetOf<f32>> Quantized for T {}
+pub trait Quantized: Scalar + Copy + Ord + Bounded + SubsetOf<i32> + SubsetOf<f32> {}
+impl<T: Scalar + Copy + Ord + Bounded + SubsetOf<i32> + SubsetOf<f32>> Quantized for T {}
+
+/// The number of values saturated to the bounds of their quantized type.
+#[cfg(feature = "saturation-counter")]
+static SATURATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of values saturated to the bounds of their quantized type, by quantization
+/// or requantization, since the start of the program or the last call to
+/// [`reset_saturation_count`].
+/// A growing count can reveal inputs outside of the distribution the model was quantized for.
+#[cfg(feature = "saturation-counter")]
+pub fn saturation_count() -> usize {
+    SATURATIONS.load(Ordering::Relaxed)
+}
+
+/// Resets the number of saturated values returned by [`saturation_count`].
+#[cfg(feature = "saturation-counter")]
+pub fn reset_saturation_count() {
+    SATURATIONS.store(0, Ordering::Relaxed);
+}
 
 /// Performs quantization on the given floating-point input.
+/// The result is saturated to the bounds of `T`.
 ///
 /// # Arguments
 /// * `input` - The input value to quantize
@@ -14,7 +37,39 @@ impl<T: Scalar + Copy + Ord + SubsetOf<i32> + SubsetOf<f32>> Quantized for T {}
 /// * `zero_point` - The quantization zero point
 ///
 pub fn quantize<T: Quantized>(input: f32, scale: f32, zero_point: T) -> T {
-    roundf(input / scale + f32::from_subset(&zero_point)).to_subset_unchecked()
+    saturate(quantize_unsaturated(input, scale, zero_point))
+}
+
+/// Performs quantization on the given floating-point input.
+/// The result is saturated to the bounds of `T` without being counted, as the saturation is
+/// inherent to the caller (e.g., the upper bound of ReLU6).
+///
+/// # Arguments
+/// * `input` - The input value to quantize
+/// * `scale` - The quantization scale
+/// * `zero_point` - The quantization zero point
+///
+pub(crate) fn quantize_uncounted<T: Quantized>(input: f32, scale: f32, zero_point: T) -> T {
+    T::from_superset_unchecked(&clamp::<T>(quantize_unsaturated(input, scale, zero_point)))
+}
+
+/// Performs quantization on the given floating-point input, without saturating the result.
+///
+/// # Arguments
+/// * `input` - The input value to quantize
+/// * `scale` - The quantization scale
+/// * `zero_point` - The quantization zero point
+///
+fn quantize_unsaturated<T: Quantized>(input: f32, scale: f32, zero_point: T) -> i32 {
+    #[cfg(not(feature = "tflite-parity"))]
+    {
+        roundf(input / scale + f32::from_subset(&zero_point)) as i32
+    }
+    #[cfg(feature = "tflite-parity")]
+    {
+        // TensorFlow Lite rounds the scaled input before offsetting it by the zero point
+        (roundf(input / scale) as i32).saturating_add(i32::from_subset(&zero_point))
+    }
 }
 
 /// Performs dequantization on the given integer input.
@@ -28,6 +83,359 @@ pub fn dequantize<T: Quantized>(input: T, scale: f32, zero_point: T) -> f32 {
     scale * (f32::from_subset(&input) - f32::from_subset(&zero_point))
 }
 
+/// Represents a real multiplier in fixed-point format, i.e., as a 32-bit multiplier in Q0.31 format
+/// combined with a power-of-two exponent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct QuantizedMultiplier {
+    pub multiplier: i32,
+    pub shift: i32,
+}
+
+impl QuantizedMultiplier {
+    /// Builds a [`QuantizedMultiplier`] from the given multiplier and shift.
+    ///
+    /// # Arguments
+    /// * `multiplier` - The Q0.31 multiplier
+    /// * `shift` - The power-of-two exponent (positive values are left shifts)
+    ///
+    pub const fn new(multiplier: i32, shift: i32) -> Self {
+        Self { multiplier, shift }
+    }
+}
+
+/// Represents the trait to requantize the 32-bit accumulators of the operators.
+/// The requantized values are not saturated, so that the operators can saturate them to the range
+/// of their fused activation function.
+/// The trait is implemented by `f32`, performing the requantization with floating-point arithmetic,
+/// and by [`QuantizedMultiplier`], performing the requantization with integer-only arithmetic.
+pub trait Requantizer: Scalar + Copy {
+    /// The type of the bias added to the accumulators.
+    type Bias: Scalar + Copy;
+
+    /// Requantizes the given accumulator.
+    ///
+    /// # Arguments
+    /// * `x` - The accumulator to requantize
+    /// * `bias` - The bias of the accumulator
+    /// * `zero_point` - The zero point of the output
+    ///
+    fn requantize<T: Quantized>(self, x: i32, bias: Self::Bias, zero_point: T) -> i32;
+
+    /// Requantizes the mean of the given accumulator.
+    ///
+    /// # Arguments
+    /// * `sum` - The accumulator to average and requantize
+    /// * `len` - The number of accumulated elements
+    /// * `bias` - The bias of the mean
+    /// * `zero_point` - The zero point of the output
+    ///
+    fn requantize_mean<T: Quantized>(
+        self,
+        sum: i32,
+        len: usize,
+        bias: Self::Bias,
+        zero_point: T,
+    ) -> i32;
+
+    /// Requantizes the sum of the given inputs.
+    ///
+    /// # Arguments
+    /// * `x` - The inputs to sum
+    /// * `zero_points` - The zero points of the inputs and of the output
+    /// * `multipliers` - The multipliers of the inputs and of the sum
+    ///
+    fn requantize_sum<T: Quantized>(
+        x: (i32, i32),
+        zero_points: (T, T, T),
+        multipliers: (Self, Self, Self),
+    ) -> i32;
+}
+
+impl Requantizer for f32 {
+    type Bias = f32;
+
+    fn requantize<T: Quantized>(self, x: i32, bias: f32, zero_point: T) -> i32 {
+        roundf(f32::from_subset(&zero_point) + bias + self * f32::from_subset(&x)) as i32
+    }
+
+    fn requantize_mean<T: Quantized>(self, sum: i32, len: usize, bias: f32, _zero_point: T) -> i32 {
+        roundf(self * (1. / len as f32 * sum as f32) + bias) as i32
+    }
+
+    fn requantize_sum<T: Quantized>(
+        x: (i32, i32),
+        _zero_points: (T, T, T),
+        multipliers: (f32, f32, f32),
+    ) -> i32 {
+        // The zero points are already folded into the last multiplier, acting as a bias
+        roundf(
+            multipliers.0 * f32::from_subset(&x.0)
+                + multipliers.1 * f32::from_subset(&x.1)
+                + multipliers.2,
+        ) as i32
+    }
+}
+
+/// The left shift applied to the inputs of [`Requantizer::requantize_sum`] in integer-only
+/// arithmetic, preserving their precision once rescaled.
+pub const SUM_LEFT_SHIFT: i32 = 20;
+
+impl Requantizer for QuantizedMultiplier {
+    type Bias = i32;
+
+    fn requantize<T: Quantized>(self, x: i32, bias: i32, zero_point: T) -> i32 {
+        i32::from_subset(&zero_point) + multiply_by_quantized_multiplier(x + bias, self)
+    }
+
+    fn requantize_mean<T: Quantized>(self, sum: i32, len: usize, bias: i32, zero_point: T) -> i32 {
+        // Round the mean half away from zero
+        let len = len as i32;
+        let mean = (sum + if sum > 0 { len / 2 } else { -len / 2 }) / len;
+        self.requantize(mean, bias, zero_point)
+    }
+
+    fn requantize_sum<T: Quantized>(
+        x: (i32, i32),
+        zero_points: (T, T, T),
+        multipliers: (
+            QuantizedMultiplier,
+            QuantizedMultiplier,
+            QuantizedMultiplier,
+        ),
+    ) -> i32 {
+        // Rescale both inputs to a common scale, sum them, and rescale the sum to the output
+        let x_0 = (x.0 - i32::from_subset(&zero_points.0)) << SUM_LEFT_SHIFT;
+        let x_1 = (x.1 - i32::from_subset(&zero_points.1)) << SUM_LEFT_SHIFT;
+        let y = multiply_by_quantized_multiplier(x_0, multipliers.0)
+            + multiply_by_quantized_multiplier(x_1, multipliers.1);
+        i32::from_subset(&zero_points.2) + multiply_by_quantized_multiplier(y, multipliers.2)
+    }
+}
+
+/// Converts the given integer to the quantized type, saturating it to the bounds of the type.
+/// With the `saturation-counter` feature, every saturated value is counted.
+///
+/// # Arguments
+/// * `x` - The integer to convert
+///
+pub(crate) fn saturate<T: Quantized>(x: i32) -> T {
+    let y = clamp::<T>(x);
+    if y != x {
+        count_saturation();
+    }
+    T::from_superset_unchecked(&y)
+}
+
+/// Counts a saturated value, if the `saturation-counter` feature is enabled.
+#[inline]
+fn count_saturation() {
+    #[cfg(feature = "saturation-counter")]
+    SATURATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Clamps the given integer to the bounds of the quantized type.
+///
+/// # Arguments
+/// * `x` - The integer to clamp
+///
+pub(crate) fn clamp<T: Quantized>(x: i32) -> i32 {
+    x.clamp(
+        i32::from_subset(&T::min_value()),
+        i32::from_subset(&T::max_value()),
+    )
+}
+
+/// Looks up the given quantized value in a table holding a result for every value of the quantized
+/// type, from the minimum to the maximum (i.e., 256 entries for 8-bit types and 65536 for `i16`).
+///
+/// # Arguments
+/// * `x` - The quantized value to look up
+/// * `table` - The lookup table
+///
+pub(crate) fn look_up<T: Quantized, const N: usize>(x: T, table: &[T; N]) -> T {
+    table[(i32::from_subset(&x) - i32::from_subset(&T::min_value())) as usize]
+}
+
+/// Multiplies the given integer by the given [`QuantizedMultiplier`], rounding to the nearest.
+/// Equivalent to the `MultiplyByQuantizedMultiplier` function of TensorFlow Lite.
+///
+/// # Arguments
+/// * `x` - The integer to multiply
+/// * `multiplier` - The fixed-point multiplier
+///
+pub fn multiply_by_quantized_multiplier(x: i32, multiplier: QuantizedMultiplier) -> i32 {
+    let left_shift = multiplier.shift.max(0);
+    let right_shift = (-multiplier.shift).max(0);
+    rounding_divide_by_pot(
+        saturating_rounding_doubling_high_mul(
+            x.wrapping_shl(left_shift as u32),
+            multiplier.multiplier,
+        ),
+        right_shift,
+    )
+}
+
+/// Returns the high 32 bits of the doubled product of the given integers, rounded to the nearest.
+/// Equivalent to the `SaturatingRoundingDoublingHighMul` function of gemmlowp.
+///
+/// # Arguments
+/// * `a` - The first factor
+/// * `b` - The second factor
+///
+pub fn saturating_rounding_doubling_high_mul(a: i32, b: i32) -> i32 {
+    if a == i32::MIN && b == i32::MIN {
+        return i32::MAX;
+    }
+    let ab = a as i64 * b as i64;
+    let nudge = if ab >= 0 { 1 << 30 } else { 1 - (1 << 30) };
+    ((ab + nudge) / (1 << 31)) as i32
+}
+
+/// Divides the given integer by the given power of two, rounding half away from zero.
+/// Equivalent to the `RoundingDivideByPOT` function of gemmlowp.
+///
+/// # Arguments
+/// * `x` - The dividend
+/// * `exponent` - The exponent of the power-of-two divisor
+///
+pub fn rounding_divide_by_pot(x: i32, exponent: i32) -> i32 {
+    // The mask is computed in 64 bits, as it would overflow for an exponent of 31
+    let mask = ((1i64 << exponent) - 1) as i32;
+    let remainder = x & mask;
+    let threshold = (mask >> 1) + (x < 0) as i32;
+    (x >> exponent) + (remainder > threshold) as i32
+}
+
+/// Multiplies the given integer by the given power of two, saturating the result.
+/// Equivalent to the `SaturatingRoundingMultiplyByPOT` function of gemmlowp for positive exponents.
+///
+/// # Arguments
+/// * `x` - The integer to multiply
+/// * `exponent` - The exponent of the power-of-two factor
+///
+fn saturating_multiply_by_pot(x: i32, exponent: i32) -> i32 {
+    let threshold = (1 << (31 - exponent)) - 1;
+    if x > threshold {
+        i32::MAX
+    } else if x < -threshold {
+        i32::MIN
+    } else {
+        x << exponent
+    }
+}
+
+/// Computes the exponential of the given negative fixed-point value.
+/// Equivalent to the `exp_on_negative_values` function of gemmlowp.
+///
+/// # Arguments
+/// * `x` - The non-positive input in Q5.26 format
+///
+/// # Returns
+/// The exponential of the input in Q0.31 format
+///
+pub fn exp_on_negative_values(x: i32) -> i32 {
+    const ONE_QUARTER: i32 = 1 << 24;
+    // Split the input into a remainder in [-1/4, 0) and multiples of powers of two
+    let x_mod_quarter_minus_one_quarter = (x & (ONE_QUARTER - 1)) - ONE_QUARTER;
+    let mut y = exp_on_interval_between_negative_one_quarter_and_0_excl(
+        saturating_multiply_by_pot(x_mod_quarter_minus_one_quarter, 5),
+    );
+    let remainder = x_mod_quarter_minus_one_quarter.wrapping_sub(x);
+    // Multiply the result by exp(-2^k) for every bit k set in the remainder
+    for (exponent, multiplier) in [
+        (-2, 1672461947),
+        (-1, 1302514674),
+        (0, 790015084),
+        (1, 290630308),
+        (2, 39332535),
+        (3, 720401),
+        (4, 242),
+    ] {
+        if remainder & (1 << (26 + exponent)) != 0 {
+            y = saturating_rounding_doubling_high_mul(y, multiplier);
+        }
+    }
+    if x == 0 {
+        i32::MAX
+    } else {
+        y
+    }
+}
+
+/// Computes the exponential of the given fixed-point value in [-1/4, 0), with a Taylor expansion
+/// around -1/8.
+///
+/// # Arguments
+/// * `x` - The input in Q0.31 format
+///
+fn exp_on_interval_between_negative_one_quarter_and_0_excl(x: i32) -> i32 {
+    const EXP_MINUS_ONE_EIGHTH: i32 = 1895147668;
+    const ONE_THIRD: i32 = 715827883;
+    let x = x + (1 << 28);
+    let x2 = saturating_rounding_doubling_high_mul(x, x);
+    let x3 = saturating_rounding_doubling_high_mul(x2, x);
+    let x4 = saturating_rounding_doubling_high_mul(x2, x2);
+    let x4_over_4 = rounding_divide_by_pot(x4, 2);
+    let x4_over_24_plus_x3_over_6_plus_x2_over_2 = rounding_divide_by_pot(
+        saturating_rounding_doubling_high_mul(x4_over_4 + x3, ONE_THIRD) + x2,
+        1,
+    );
+    EXP_MINUS_ONE_EIGHTH.wrapping_add(saturating_rounding_doubling_high_mul(
+        EXP_MINUS_ONE_EIGHTH,
+        x.wrapping_add(x4_over_24_plus_x3_over_6_plus_x2_over_2),
+    ))
+}
+
+/// Computes the reciprocal of the given fixed-point value.
+/// Equivalent to the `GetReciprocal` function of TensorFlow Lite.
+///
+/// # Arguments
+/// * `x` - The positive input
+/// * `integer_bits` - The number of integer bits of the input
+///
+/// # Returns
+/// The reciprocal in Q0.31 format, scaled by a power of two, and the exponent of the power of two
+///
+pub fn reciprocal(x: i32, integer_bits: i32) -> (i32, i32) {
+    let headroom = (x as u32).leading_zeros();
+    // Normalize the input to [1, 2) and compute 1 / (1 + x) for x in [0, 1)
+    let x = ((x as u32) << headroom).wrapping_sub(1 << 31) as i32;
+    (
+        one_over_one_plus_x_for_x_in_0_1(x),
+        integer_bits - headroom as i32,
+    )
+}
+
+/// Computes 1 / (1 + x) for the given fixed-point value in [0, 1), with the Newton-Raphson method.
+/// Equivalent to the `one_over_one_plus_x_for_x_in_0_1` function of gemmlowp.
+///
+/// # Arguments
+/// * `x` - The input in Q0.31 format
+///
+/// # Returns
+/// The result in Q0.31 format
+///
+fn one_over_one_plus_x_for_x_in_0_1(x: i32) -> i32 {
+    const FORTY_EIGHT_OVER_SEVENTEEN: i32 = 1515870810;
+    const NEG_THIRTY_TWO_OVER_SEVENTEEN: i32 = -1010580540;
+    const ONE_Q2_29: i32 = 1 << 29;
+    // Compute (1 + x) / 2, rounding half away from zero
+    let half_denominator = ((x as i64 + i32::MAX as i64 + 1) / 2) as i32;
+    let mut y = FORTY_EIGHT_OVER_SEVENTEEN.wrapping_add(saturating_rounding_doubling_high_mul(
+        half_denominator,
+        NEG_THIRTY_TWO_OVER_SEVENTEEN,
+    ));
+    for _ in 0..3 {
+        let half_denominator_times_y = saturating_rounding_doubling_high_mul(half_denominator, y);
+        let one_minus_half_denominator_times_y = ONE_Q2_29.wrapping_sub(half_denominator_times_y);
+        y = y.wrapping_add(saturating_multiply_by_pot(
+            saturating_rounding_doubling_high_mul(y, one_minus_half_denominator_times_y),
+            2,
+        ));
+    }
+    saturating_multiply_by_pot(y, 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,6 +451,67 @@ mod tests {
         assert_eq!(quantize(VALUE, SCALE, ZERO_POINT), VALUE_QUANTIZED);
     }
 
+    #[test]
+    fn quantize_saturated() {
+        assert_eq!(quantize(100., SCALE, ZERO_POINT), i8::MAX);
+        assert_eq!(quantize(-100., SCALE, ZERO_POINT), i8::MIN);
+        assert_eq!(quantize(100., SCALE, 0u8), u8::MAX);
+    }
+
+    #[test]
+    fn saturate_value() {
+        assert_eq!(saturate::<i8>(1000), i8::MAX);
+        assert_eq!(saturate::<i8>(-1000), i8::MIN);
+        assert_eq!(saturate::<u8>(-1), u8::MIN);
+        assert_eq!(saturate::<i8>(42), 42);
+    }
+
+    #[test]
+    fn requantize_integer() {
+        // 0.3 = 0.6 * 2^-1
+        let multiplier = QuantizedMultiplier::new(1288490189, -1);
+        assert_eq!(multiplier.requantize(100, -10, ZERO_POINT), 30);
+        assert_eq!(multiplier.requantize(-100, 10, ZERO_POINT), -24);
+        assert_eq!(multiplier.requantize_mean(49, 10, 0, ZERO_POINT), 5);
+    }
+
+    #[test]
+    fn requantize_sum_integer() {
+        // 0.5 = 0.5 * 2^0, 0.25 = 0.5 * 2^-1, 2^-19 = 0.5 * 2^-18
+        let multipliers = (
+            QuantizedMultiplier::new(1 << 30, 0),
+            QuantizedMultiplier::new(1 << 30, -1),
+            QuantizedMultiplier::new(1 << 30, -18),
+        );
+        assert_eq!(
+            QuantizedMultiplier::requantize_sum((5, 7), (1, 2, ZERO_POINT), multipliers),
+            10
+        );
+    }
+
+    #[test]
+    fn rounding_divide_by_pot_value() {
+        assert_eq!(rounding_divide_by_pot(5, 1), 3);
+        assert_eq!(rounding_divide_by_pot(-5, 1), -3);
+        assert_eq!(rounding_divide_by_pot(1 << 30, 31), 1);
+        assert_eq!(rounding_divide_by_pot(-1 << 30, 31), -1);
+        assert_eq!(rounding_divide_by_pot(i32::MIN, 31), -1);
+    }
+
+    #[test]
+    fn exp_on_negative_values_fixed_point() {
+        assert_eq!(exp_on_negative_values(0), i32::MAX);
+        // exp(-1) = 0.36787944, exp(-1.5) = 0.22313016
+        assert_eq!(exp_on_negative_values(-1 << 26), 790015308);
+        assert_eq!(exp_on_negative_values(-3 << 25), 479168506);
+    }
+
+    #[test]
+    fn reciprocal_fixed_point() {
+        // 1 / 3 = 0.66666667 * 2^-1
+        assert_eq!(reciprocal(3 << 19, 12), (1431655762, 1));
+    }
+
     #[test]
     fn dequantize_value() {
         assert_eq!(
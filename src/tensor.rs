@@ -9,11 +9,33 @@ use crate::quantize::{dequantize, quantize, Quantized};
 pub enum TensorViewPadding {
     /// In the 'Same' padding, the [`TensorView`] is allowed to exceed the input bounds.
     /// The exceeding values will be replaced by zeros.
+    /// As in TensorFlow Lite, the excess padding is placed on the bottom and on the right.
     Same,
     /// In the 'Valid' padding, the [`TensorView`] will always remain in the input bounds.
     Valid,
 }
 
+impl TensorViewPadding {
+    /// Computes the padding of a tensor dimension as TensorFlow Lite does, i.e., just enough to
+    /// cover the dimension with the strided views, placing the excess padding after it.
+    /// Returns the amounts of padding before and after the dimension, respectively.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the tensor dimension
+    /// * `extent` - The extent of the view on the dimension, enlarged by the dilation
+    /// * `stride` - The view stride on the dimension
+    ///
+    pub const fn amounts(&self, size: usize, extent: usize, stride: usize) -> (usize, usize) {
+        match self {
+            Self::Same => {
+                let total = ((size.div_ceil(stride) - 1) * stride + extent).saturating_sub(size);
+                (total / 2, total - total / 2)
+            }
+            Self::Valid => (0, 0),
+        }
+    }
+}
+
 /// Represents the tensor view, i.e., the input region extracted from the tensor.
 pub struct TensorView<T: Quantized, const ROWS: usize, const COLS: usize, const CHANS: usize> {
     pub buffer: Buffer2D<[T; CHANS], ROWS, COLS>,
@@ -21,6 +43,102 @@ pub struct TensorView<T: Quantized, const ROWS: usize, const COLS: usize, const
     pub len: usize,
 }
 
+/// Represents the trait to combine two tensors element-wise.
+/// The dimensions of size 1 are broadcasted to the size of the output, following the
+/// TensorFlow Lite broadcasting rules.
+pub trait TensorBroadcast<T: Quantized, Rhs, Output> {
+    /// Combines [`Self`] with the given tensor element-wise, writing the result into the given
+    /// tensor, quantized with the given scale and zero point.
+    ///
+    /// # Arguments
+    /// * `rhs` - The right-hand side tensor
+    /// * `f` - The function combining the quantized elements of the two tensors
+    /// * `scale` - The scale of the resulting tensor
+    /// * `zero_point` - The zero point of the resulting tensor
+    /// * `output` - The resulting tensor
+    ///
+    fn broadcast_into<F: Fn(T, T) -> T>(
+        &self,
+        rhs: &Rhs,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Output,
+    );
+
+    /// Returns the zero points of [`Self`] and of the given tensor.
+    ///
+    /// # Arguments
+    /// * `rhs` - The right-hand side tensor
+    ///
+    fn zero_points(&self, rhs: &Rhs) -> (T, T);
+}
+
+/// Represents the trait to transform a tensor element-wise.
+/// The resulting tensor has the same shape, but it may be of a different quantized type.
+pub trait TensorMap<T: Quantized, U: Quantized, Output> {
+    /// Transforms [`Self`] element-wise, writing the result into the given tensor, quantized with
+    /// the given scale and zero point.
+    ///
+    /// # Arguments
+    /// * `f` - The function transforming the quantized elements of the tensor
+    /// * `scale` - The scale of the resulting tensor
+    /// * `zero_point` - The zero point of the resulting tensor
+    /// * `output` - The resulting tensor
+    ///
+    fn map_into<F: Fn(T) -> U>(
+        &self,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [U; 1],
+        output: &mut Output,
+    );
+}
+
+/// Represents the trait to copy the elements of a tensor into a tensor of a different shape, but
+/// with the same number of elements, in row-major order.
+pub trait TensorReshape<Output> {
+    /// Copies the elements of [`Self`] into the given tensor, along with its scale and zero point.
+    ///
+    /// # Arguments
+    /// * `output` - The resulting tensor
+    ///
+    fn reshape_into(&self, output: &mut Output);
+}
+
+/// Represents the trait to read a tensor as a 2-dimensional tensor, flattening all its dimensions
+/// but the first one in row-major order.
+pub trait TensorFlatten<T: Quantized, const ROWS: usize, const COLS: usize> {
+    /// Returns the element of [`Self`] at the given index of the flattened tensor.
+    ///
+    /// # Arguments
+    /// * `index` - The row and the column of the element in the flattened tensor
+    ///
+    fn get_flat(&self, index: (usize, usize)) -> T;
+}
+
+/// Represents the trait to transform a tensor along its last axis, i.e., the columns of a
+/// [`Tensor2D`] and the channels of a [`Tensor4D`].
+/// The resulting tensor has the same shape and the same quantized type.
+pub trait TensorMapLastAxis<T: Quantized, const LEN: usize>: Sized {
+    /// Transforms each vector of [`Self`] along the last axis, writing the result into the given
+    /// tensor, quantized with the given scale and zero point.
+    ///
+    /// # Arguments
+    /// * `f` - The function transforming the vectors of quantized elements of the tensor
+    /// * `scale` - The scale of the resulting tensor
+    /// * `zero_point` - The zero point of the resulting tensor
+    /// * `output` - The resulting tensor
+    ///
+    fn map_last_axis_into<F: Fn([T; LEN]) -> [T; LEN]>(
+        &self,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Self,
+    );
+}
+
 /// Represents a quantized 2-dimensional tensor.
 /// The tensor is composed by a 2-dimensional matrix.
 #[derive(Debug, PartialEq)]
@@ -69,6 +187,16 @@ impl<T: Quantized, const ROWS: usize, const COLS: usize, const QUANTS: usize>
     }
 }
 
+impl<T: Quantized, const ROWS: usize, const COLS: usize, const QUANTS: usize> Default
+    for Tensor2D<T, ROWS, COLS, QUANTS>
+{
+    /// Builds a [`Tensor2D`] filled with zeros, with zero scales and zero points.
+    fn default() -> Self {
+        let zero = T::from_superset_unchecked(&0);
+        Self::new(Buffer2D::from_element(zero), [0.; QUANTS], [zero; QUANTS])
+    }
+}
+
 impl<T: Quantized, const ROWS: usize, const COLS: usize> Tensor2D<T, ROWS, COLS, 1> {
     /// Builds a quantized [`Tensor2D`] from a dequantized [`Buffer2D`].
     ///
@@ -77,7 +205,11 @@ impl<T: Quantized, const ROWS: usize, const COLS: usize> Tensor2D<T, ROWS, COLS,
     /// * `scale` - The quantization scale
     /// * `zero_point` - The quantization zero point
     ///
-    pub fn quantize(input: Buffer2D<f32, ROWS, COLS>, scale: [f32; 1], zero_point: [T; 1]) -> Self {
+    pub fn quantize(
+        input: &Buffer2D<f32, ROWS, COLS>,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+    ) -> Self {
         Self::new(
             input.map(|f| quantize(f, scale[0], zero_point[0])),
             scale,
@@ -85,11 +217,97 @@ impl<T: Quantized, const ROWS: usize, const COLS: usize> Tensor2D<T, ROWS, COLS,
         )
     }
 
+    /// Quantizes the given [`Buffer2D`] into [`Self`], without moving it.
+    ///
+    /// # Arguments
+    /// * `input` - The input buffer as a [`Buffer2D`]
+    /// * `scale` - The quantization scale
+    /// * `zero_point` - The quantization zero point
+    ///
+    pub fn quantize_from(
+        &mut self,
+        input: &Buffer2D<f32, ROWS, COLS>,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+    ) {
+        for (q, &f) in self.buffer.iter_mut().zip(input.iter()) {
+            *q = quantize(f, scale[0], zero_point[0]);
+        }
+        self.scale = scale;
+        self.zero_point = zero_point;
+    }
+
+    /// Copies the given quantized [`Buffer2D`] into [`Self`], along with the given scale and zero
+    /// point, without moving it.
+    ///
+    /// # Arguments
+    /// * `input` - The quantized input buffer as a [`Buffer2D`]
+    /// * `scale` - The quantization scale
+    /// * `zero_point` - The quantization zero point
+    ///
+    pub fn copy_from(
+        &mut self,
+        input: &Buffer2D<T, ROWS, COLS>,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+    ) {
+        self.buffer.copy_from(input);
+        self.scale = scale;
+        self.zero_point = zero_point;
+    }
+
     /// Returns a dequantized [`Buffer2D`] from [`Self`].
     pub fn dequantize(&self) -> Buffer2D<f32, ROWS, COLS> {
         self.buffer
             .map(|q| dequantize(q, self.scale[0], self.zero_point[0]))
     }
+
+    /// Dequantizes [`Self`] into the given [`Buffer2D`], without moving it.
+    ///
+    /// # Arguments
+    /// * `output` - The buffer receiving the dequantized values
+    ///
+    pub fn dequantize_into(&self, output: &mut Buffer2D<f32, ROWS, COLS>) {
+        for (f, &q) in output.iter_mut().zip(self.buffer.iter()) {
+            *f = dequantize(q, self.scale[0], self.zero_point[0]);
+        }
+    }
+}
+
+impl<
+        T: Quantized,
+        const ROWS_1: usize,
+        const COLS_1: usize,
+        const ROWS_2: usize,
+        const COLS_2: usize,
+        const ROWS: usize,
+        const COLS: usize,
+    > TensorBroadcast<T, Tensor2D<T, ROWS_2, COLS_2, 1>, Tensor2D<T, ROWS, COLS, 1>>
+    for Tensor2D<T, ROWS_1, COLS_1, 1>
+{
+    fn broadcast_into<F: Fn(T, T) -> T>(
+        &self,
+        rhs: &Tensor2D<T, ROWS_2, COLS_2, 1>,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Tensor2D<T, ROWS, COLS, 1>,
+    ) {
+        for i in 0..ROWS {
+            for j in 0..COLS {
+                output.buffer[(i, j)] = f(
+                    self.buffer[(broadcast_index(i, ROWS_1), broadcast_index(j, COLS_1))],
+                    rhs.buffer[(broadcast_index(i, ROWS_2), broadcast_index(j, COLS_2))],
+                );
+            }
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+
+    fn zero_points(&self, rhs: &Tensor2D<T, ROWS_2, COLS_2, 1>) -> (T, T) {
+        (self.zero_point[0], rhs.zero_point[0])
+    }
 }
 
 impl<
@@ -104,6 +322,22 @@ impl<
     for Tensor2D<T, BATCHES, OUTPUT_COLS, QUANTS>
 {
     fn from(tensor: Tensor4D<T, BATCHES, ROWS, COLS, CHANS, QUANTS>) -> Self {
+        Self::from(&tensor)
+    }
+}
+
+impl<
+        T: Quantized,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+        const QUANTS: usize,
+        const OUTPUT_COLS: usize,
+    > From<&Tensor4D<T, BATCHES, ROWS, COLS, CHANS, QUANTS>>
+    for Tensor2D<T, BATCHES, OUTPUT_COLS, QUANTS>
+{
+    fn from(tensor: &Tensor4D<T, BATCHES, ROWS, COLS, CHANS, QUANTS>) -> Self {
         Self::new(
             Buffer2D::from_fn(|i, j| {
                 tensor.buffer[i][(j / (CHANS * COLS), j / CHANS % COLS)][j % CHANS]
@@ -114,6 +348,91 @@ impl<
     }
 }
 
+impl<T: Quantized, U: Quantized, const ROWS: usize, const COLS: usize>
+    TensorMap<T, U, Tensor2D<U, ROWS, COLS, 1>> for Tensor2D<T, ROWS, COLS, 1>
+{
+    fn map_into<F: Fn(T) -> U>(
+        &self,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [U; 1],
+        output: &mut Tensor2D<U, ROWS, COLS, 1>,
+    ) {
+        for (y, &x) in output.buffer.iter_mut().zip(self.buffer.iter()) {
+            *y = f(x);
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+}
+
+impl<
+        T: Quantized,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+        const QUANTS: usize,
+        const OUTPUT_COLS: usize,
+    > TensorReshape<Tensor2D<T, BATCHES, OUTPUT_COLS, QUANTS>>
+    for Tensor4D<T, BATCHES, ROWS, COLS, CHANS, QUANTS>
+{
+    fn reshape_into(&self, output: &mut Tensor2D<T, BATCHES, OUTPUT_COLS, QUANTS>) {
+        for i in 0..BATCHES {
+            for j in 0..OUTPUT_COLS {
+                output.buffer[(i, j)] =
+                    TensorFlatten::<T, BATCHES, OUTPUT_COLS>::get_flat(self, (i, j));
+            }
+        }
+        output.scale = self.scale;
+        output.zero_point = self.zero_point;
+    }
+}
+
+impl<T: Quantized, const ROWS: usize, const COLS: usize, const QUANTS: usize>
+    TensorFlatten<T, ROWS, COLS> for Tensor2D<T, ROWS, COLS, QUANTS>
+{
+    fn get_flat(&self, index: (usize, usize)) -> T {
+        self.buffer[index]
+    }
+}
+
+impl<
+        T: Quantized,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+        const QUANTS: usize,
+        const OUTPUT_COLS: usize,
+    > TensorFlatten<T, BATCHES, OUTPUT_COLS> for Tensor4D<T, BATCHES, ROWS, COLS, CHANS, QUANTS>
+{
+    fn get_flat(&self, (i, j): (usize, usize)) -> T {
+        self.buffer[i][(j / (CHANS * COLS), j / CHANS % COLS)][j % CHANS]
+    }
+}
+
+impl<T: Quantized, const ROWS: usize, const COLS: usize> TensorMapLastAxis<T, COLS>
+    for Tensor2D<T, ROWS, COLS, 1>
+{
+    fn map_last_axis_into<F: Fn([T; COLS]) -> [T; COLS]>(
+        &self,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Self,
+    ) {
+        for i in 0..ROWS {
+            let row = f(array::from_fn(|j| self.buffer[(i, j)]));
+            for (j, y) in row.into_iter().enumerate() {
+                output.buffer[(i, j)] = y;
+            }
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+}
+
 impl<
         T: Quantized,
         const ROWS: usize,
@@ -126,6 +445,22 @@ impl<
     for Tensor4D<T, ROWS, OUTPUT_ROWS, OUTPUT_COLS, OUTPUT_CHANS, QUANTS>
 {
     fn from(tensor: Tensor2D<T, ROWS, COLS, QUANTS>) -> Self {
+        Self::from(&tensor)
+    }
+}
+
+impl<
+        T: Quantized,
+        const ROWS: usize,
+        const COLS: usize,
+        const QUANTS: usize,
+        const OUTPUT_ROWS: usize,
+        const OUTPUT_COLS: usize,
+        const OUTPUT_CHANS: usize,
+    > From<&Tensor2D<T, ROWS, COLS, QUANTS>>
+    for Tensor4D<T, ROWS, OUTPUT_ROWS, OUTPUT_COLS, OUTPUT_CHANS, QUANTS>
+{
+    fn from(tensor: &Tensor2D<T, ROWS, COLS, QUANTS>) -> Self {
         Self::new(
             array::from_fn(|b| {
                 Buffer2D::from_fn(|i, j| {
@@ -140,6 +475,56 @@ impl<
     }
 }
 
+impl<
+        T: Quantized,
+        const ROWS: usize,
+        const COLS: usize,
+        const QUANTS: usize,
+        const OUTPUT_ROWS: usize,
+        const OUTPUT_COLS: usize,
+        const OUTPUT_CHANS: usize,
+    > TensorReshape<Tensor4D<T, ROWS, OUTPUT_ROWS, OUTPUT_COLS, OUTPUT_CHANS, QUANTS>>
+    for Tensor2D<T, ROWS, COLS, QUANTS>
+{
+    fn reshape_into(
+        &self,
+        output: &mut Tensor4D<T, ROWS, OUTPUT_ROWS, OUTPUT_COLS, OUTPUT_CHANS, QUANTS>,
+    ) {
+        for (b, matrix) in output.buffer.iter_mut().enumerate() {
+            for i in 0..OUTPUT_ROWS {
+                for j in 0..OUTPUT_COLS {
+                    for (c, y) in matrix[(i, j)].iter_mut().enumerate() {
+                        *y =
+                            self.buffer[(b, OUTPUT_CHANS * OUTPUT_COLS * i + OUTPUT_CHANS * j + c)];
+                    }
+                }
+            }
+        }
+        output.scale = self.scale;
+        output.zero_point = self.zero_point;
+    }
+}
+
+impl<
+        T: Quantized,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+        const QUANTS: usize,
+    > Default for Tensor4D<T, BATCHES, ROWS, COLS, CHANS, QUANTS>
+{
+    /// Builds a [`Tensor4D`] filled with zeros, with zero scales and zero points.
+    fn default() -> Self {
+        let zero = T::from_superset_unchecked(&0);
+        Self::new(
+            [Buffer2D::from_element([zero; CHANS]); BATCHES],
+            [0.; QUANTS],
+            [zero; QUANTS],
+        )
+    }
+}
+
 impl<
         T: Quantized,
         const BATCHES: usize,
@@ -176,6 +561,7 @@ impl<
     /// * `batch` - The tensor batch from which to extract the view
     /// * `padding` - The view padding as a [`TensorViewPadding`] enum
     /// * `strides` - The view strides on the width and height of the tensor, repectively
+    /// * `dilations` - The view dilations on the width and height of the tensor, respectively
     ///
     pub fn view<const VIEW_ROWS: usize, const VIEW_COLS: usize>(
         &self,
@@ -183,17 +569,28 @@ impl<
         batch: usize,
         padding: TensorViewPadding,
         strides: (usize, usize),
+        dilations: (usize, usize),
     ) -> TensorView<T, VIEW_ROWS, VIEW_COLS, CHANS> {
         let mut len = VIEW_ROWS * VIEW_COLS;
+        // The extent of the view on the tensor, enlarged by the dilations
+        let extent = (
+            (VIEW_ROWS - 1) * dilations.0 + 1,
+            (VIEW_COLS - 1) * dilations.1 + 1,
+        );
         let mut mask = Buffer2D::from_element(true);
         TensorView {
             buffer: Buffer2D::from_fn(|m, n| match padding {
                 TensorViewPadding::Same => {
-                    // Compute the index shift based on the view dimensions
-                    let shift = ((VIEW_ROWS - 1) / 2, (VIEW_COLS - 1) / 2);
+                    // Shift the indexes by the padding before the tensor
+                    let shift = (
+                        padding.amounts(ROWS, extent.0, strides.0).0,
+                        padding.amounts(COLS, extent.1, strides.1).0,
+                    );
                     let index = (
                         // If the calculated index falls within the tensor bounds, keep it
-                        if let Some(x) = (strides.0 * focus.0 + m).checked_sub(shift.0) {
+                        if let Some(x) =
+                            (strides.0 * focus.0 + dilations.0 * m).checked_sub(shift.0)
+                        {
                             x
                         // Otherwise, return zero (as per "same" padding)
                         } else {
@@ -202,7 +599,9 @@ impl<
                             return [T::from_superset_unchecked(&0); CHANS];
                         },
                         // Same for the other index value
-                        if let Some(x) = (strides.1 * focus.1 + n).checked_sub(shift.1) {
+                        if let Some(x) =
+                            (strides.1 * focus.1 + dilations.1 * n).checked_sub(shift.1)
+                        {
                             x
                         } else {
                             len -= 1;
@@ -219,7 +618,10 @@ impl<
                 }
                 TensorViewPadding::Valid => {
                     // For "valid" paddings, directly extract the view for valid indexes only
-                    self.buffer[batch][(strides.0 * focus.0 + m, strides.1 * focus.1 + n)]
+                    self.buffer[batch][(
+                        strides.0 * focus.0 + dilations.0 * m,
+                        strides.1 * focus.1 + dilations.1 * n,
+                    )]
                 }
             }),
             mask,
@@ -228,6 +630,197 @@ impl<
     }
 }
 
+impl<
+        T: Quantized,
+        const BATCHES_1: usize,
+        const ROWS_1: usize,
+        const COLS_1: usize,
+        const CHANS_1: usize,
+        const BATCHES_2: usize,
+        const ROWS_2: usize,
+        const COLS_2: usize,
+        const CHANS_2: usize,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+    >
+    TensorBroadcast<
+        T,
+        Tensor4D<T, BATCHES_2, ROWS_2, COLS_2, CHANS_2, 1>,
+        Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>,
+    > for Tensor4D<T, BATCHES_1, ROWS_1, COLS_1, CHANS_1, 1>
+{
+    fn broadcast_into<F: Fn(T, T) -> T>(
+        &self,
+        rhs: &Tensor4D<T, BATCHES_2, ROWS_2, COLS_2, CHANS_2, 1>,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>,
+    ) {
+        for b in 0..BATCHES {
+            for i in 0..ROWS {
+                for j in 0..COLS {
+                    let x1 = &self.buffer[broadcast_index(b, BATCHES_1)]
+                        [(broadcast_index(i, ROWS_1), broadcast_index(j, COLS_1))];
+                    let x2 = &rhs.buffer[broadcast_index(b, BATCHES_2)]
+                        [(broadcast_index(i, ROWS_2), broadcast_index(j, COLS_2))];
+                    for (c, y) in output.buffer[b][(i, j)].iter_mut().enumerate() {
+                        *y = f(
+                            x1[broadcast_index(c, CHANS_1)],
+                            x2[broadcast_index(c, CHANS_2)],
+                        );
+                    }
+                }
+            }
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+
+    fn zero_points(&self, rhs: &Tensor4D<T, BATCHES_2, ROWS_2, COLS_2, CHANS_2, 1>) -> (T, T) {
+        (self.zero_point[0], rhs.zero_point[0])
+    }
+}
+
+impl<
+        T: Quantized,
+        const BATCHES_1: usize,
+        const ROWS_1: usize,
+        const COLS_1: usize,
+        const CHANS_1: usize,
+        const ROWS_2: usize,
+        const COLS_2: usize,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+    > TensorBroadcast<T, Tensor2D<T, ROWS_2, COLS_2, 1>, Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>>
+    for Tensor4D<T, BATCHES_1, ROWS_1, COLS_1, CHANS_1, 1>
+{
+    fn broadcast_into<F: Fn(T, T) -> T>(
+        &self,
+        rhs: &Tensor2D<T, ROWS_2, COLS_2, 1>,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>,
+    ) {
+        // The rows and the columns of the right-hand side tensor are its last two dimensions, i.e.,
+        // its shape is padded to [1, 1, ROWS_2, COLS_2]
+        for b in 0..BATCHES {
+            for i in 0..ROWS {
+                for j in 0..COLS {
+                    let x1 = &self.buffer[broadcast_index(b, BATCHES_1)]
+                        [(broadcast_index(i, ROWS_1), broadcast_index(j, COLS_1))];
+                    for (c, y) in output.buffer[b][(i, j)].iter_mut().enumerate() {
+                        *y = f(
+                            x1[broadcast_index(c, CHANS_1)],
+                            rhs.buffer[(broadcast_index(j, ROWS_2), broadcast_index(c, COLS_2))],
+                        );
+                    }
+                }
+            }
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+
+    fn zero_points(&self, rhs: &Tensor2D<T, ROWS_2, COLS_2, 1>) -> (T, T) {
+        (self.zero_point[0], rhs.zero_point[0])
+    }
+}
+
+impl<
+        T: Quantized,
+        const ROWS_1: usize,
+        const COLS_1: usize,
+        const BATCHES_2: usize,
+        const ROWS_2: usize,
+        const COLS_2: usize,
+        const CHANS_2: usize,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+    >
+    TensorBroadcast<
+        T,
+        Tensor4D<T, BATCHES_2, ROWS_2, COLS_2, CHANS_2, 1>,
+        Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>,
+    > for Tensor2D<T, ROWS_1, COLS_1, 1>
+{
+    fn broadcast_into<F: Fn(T, T) -> T>(
+        &self,
+        rhs: &Tensor4D<T, BATCHES_2, ROWS_2, COLS_2, CHANS_2, 1>,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>,
+    ) {
+        rhs.broadcast_into(self, |x2, x1| f(x1, x2), scale, zero_point, output)
+    }
+
+    fn zero_points(&self, rhs: &Tensor4D<T, BATCHES_2, ROWS_2, COLS_2, CHANS_2, 1>) -> (T, T) {
+        (self.zero_point[0], rhs.zero_point[0])
+    }
+}
+
+impl<
+        T: Quantized,
+        U: Quantized,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+    > TensorMap<T, U, Tensor4D<U, BATCHES, ROWS, COLS, CHANS, 1>>
+    for Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>
+{
+    fn map_into<F: Fn(T) -> U>(
+        &self,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [U; 1],
+        output: &mut Tensor4D<U, BATCHES, ROWS, COLS, CHANS, 1>,
+    ) {
+        for (output_matrix, matrix) in output.buffer.iter_mut().zip(self.buffer.iter()) {
+            for (output_array, array) in output_matrix.iter_mut().zip(matrix.iter()) {
+                for (y, &x) in output_array.iter_mut().zip(array.iter()) {
+                    *y = f(x);
+                }
+            }
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+}
+
+impl<
+        T: Quantized,
+        const BATCHES: usize,
+        const ROWS: usize,
+        const COLS: usize,
+        const CHANS: usize,
+    > TensorMapLastAxis<T, CHANS> for Tensor4D<T, BATCHES, ROWS, COLS, CHANS, 1>
+{
+    fn map_last_axis_into<F: Fn([T; CHANS]) -> [T; CHANS]>(
+        &self,
+        f: F,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+        output: &mut Self,
+    ) {
+        for (output_matrix, matrix) in output.buffer.iter_mut().zip(self.buffer.iter()) {
+            for (output_array, array) in output_matrix.iter_mut().zip(matrix.iter()) {
+                *output_array = f(*array);
+            }
+        }
+        output.scale = scale;
+        output.zero_point = zero_point;
+    }
+}
+
 impl<
         T: Quantized,
         const BATCHES: usize,
@@ -244,7 +837,7 @@ impl<
     /// * `zero_point` - The quantization zero point
     ///
     pub fn quantize(
-        input: Buffer4D<f32, BATCHES, ROWS, COLS, CHANS>,
+        input: &Buffer4D<f32, BATCHES, ROWS, COLS, CHANS>,
         scale: [f32; 1],
         zero_point: [T; 1],
     ) -> Self {
@@ -255,11 +848,85 @@ impl<
         )
     }
 
+    /// Quantizes the given [`Buffer4D`] into [`Self`], without moving it.
+    ///
+    /// # Arguments
+    /// * `input` - The input buffer as a [`Buffer4D`]
+    /// * `scale` - The quantization scale
+    /// * `zero_point` - The quantization zero point
+    ///
+    pub fn quantize_from(
+        &mut self,
+        input: &Buffer4D<f32, BATCHES, ROWS, COLS, CHANS>,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+    ) {
+        for (matrix, input_matrix) in self.buffer.iter_mut().zip(input.iter()) {
+            for (array, input_array) in matrix.iter_mut().zip(input_matrix.iter()) {
+                for (q, &f) in array.iter_mut().zip(input_array.iter()) {
+                    *q = quantize(f, scale[0], zero_point[0]);
+                }
+            }
+        }
+        self.scale = scale;
+        self.zero_point = zero_point;
+    }
+
+    /// Copies the given quantized [`Buffer4D`] into [`Self`], along with the given scale and zero
+    /// point, without moving it.
+    ///
+    /// # Arguments
+    /// * `input` - The quantized input buffer as a [`Buffer4D`]
+    /// * `scale` - The quantization scale
+    /// * `zero_point` - The quantization zero point
+    ///
+    pub fn copy_from(
+        &mut self,
+        input: &Buffer4D<T, BATCHES, ROWS, COLS, CHANS>,
+        scale: [f32; 1],
+        zero_point: [T; 1],
+    ) {
+        for (matrix, input_matrix) in self.buffer.iter_mut().zip(input.iter()) {
+            matrix.copy_from(input_matrix);
+        }
+        self.scale = scale;
+        self.zero_point = zero_point;
+    }
+
     /// Returns a dequantized [`Buffer2D`] from [`Self`].
     pub fn dequantize(&self) -> Buffer4D<f32, BATCHES, ROWS, COLS, CHANS> {
         self.buffer
             .map(|m| m.map(|a| a.map(|q| dequantize(q, self.scale[0], self.zero_point[0]))))
     }
+
+    /// Dequantizes [`Self`] into the given [`Buffer4D`], without moving it.
+    ///
+    /// # Arguments
+    /// * `output` - The buffer receiving the dequantized values
+    ///
+    pub fn dequantize_into(&self, output: &mut Buffer4D<f32, BATCHES, ROWS, COLS, CHANS>) {
+        for (output_matrix, matrix) in output.iter_mut().zip(self.buffer.iter()) {
+            for (output_array, array) in output_matrix.iter_mut().zip(matrix.iter()) {
+                for (f, &q) in output_array.iter_mut().zip(array.iter()) {
+                    *f = dequantize(q, self.scale[0], self.zero_point[0]);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the index along a dimension of the given size, broadcasting it if the size is 1.
+///
+/// # Arguments
+/// * `index` - The index along the broadcasted dimension
+/// * `size` - The size of the dimension
+///
+fn broadcast_index(index: usize, size: usize) -> usize {
+    if size == 1 {
+        0
+    } else {
+        index
+    }
 }
 
 #[cfg(test)]
@@ -315,6 +982,18 @@ mod tests {
     ];
     const TENSOR_4D_VIEW_LEN: usize = 3;
 
+    const TENSOR_4D_BROADCAST_BUFFER: Buffer4D<i8, 1, 1, 1, 2> = [matrix![[1, 2]]];
+    const TENSOR_4D_BROADCAST_OUTPUT_BUFFER: Buffer4D<i8, 2, 2, 3, 2> = [
+        matrix![
+            [31, 36], [39, 44], [47, 52];
+            [55, 60], [63, 68], [71, 76]
+        ],
+        matrix![
+            [79,  84],  [87,  92],  [95,  100];
+            [103, 108], [111, 116], [119, 124]
+        ],
+    ];
+
     const TENSOR_4D_TO_TENSOR_2D_BUFFER: Buffer2D<i8, 2, 12> = matrix![
         30, 34, 38, 42, 46, 50, 54,  58,  62,  66,  70,  74;
         78, 82, 86, 90, 94, 98, 102, 106, 110, 114, 118, 122
@@ -334,10 +1013,29 @@ mod tests {
 
     #[test]
     fn tensor_2d_quantize() {
-        let tensor = Tensor2D::quantize(TENSOR_2D_BUFFER, TENSOR_2D_SCALE, TENSOR_2D_ZERO_POINT);
+        let tensor = Tensor2D::quantize(&TENSOR_2D_BUFFER, TENSOR_2D_SCALE, TENSOR_2D_ZERO_POINT);
         assert_eq!(tensor.buffer, TENSOR_2D_BUFFER_QUANTIZED);
     }
 
+    #[test]
+    fn tensor_2d_quantize_from() {
+        let mut tensor = Tensor2D::default();
+        tensor.quantize_from(&TENSOR_2D_BUFFER, TENSOR_2D_SCALE, TENSOR_2D_ZERO_POINT);
+        assert_eq!(
+            tensor,
+            Tensor2D::new(
+                TENSOR_2D_BUFFER_QUANTIZED,
+                TENSOR_2D_SCALE,
+                TENSOR_2D_ZERO_POINT
+            )
+        );
+        tensor.copy_from(&TENSOR_2D_BUFFER_QUANTIZED, [0.5], [1]);
+        assert_eq!(
+            tensor,
+            Tensor2D::new(TENSOR_2D_BUFFER_QUANTIZED, [0.5], [1])
+        );
+    }
+
     #[test]
     fn tensor_2d_dequantize() {
         let tensor = Tensor2D::new(
@@ -348,6 +1046,18 @@ mod tests {
         assert_eq!(tensor.dequantize(), TENSOR_2D_BUFFER_DEQUANTIZED);
     }
 
+    #[test]
+    fn tensor_2d_dequantize_into() {
+        let tensor = Tensor2D::new(
+            TENSOR_2D_BUFFER_QUANTIZED,
+            TENSOR_2D_SCALE,
+            TENSOR_2D_ZERO_POINT,
+        );
+        let mut output = Buffer2D::zeros();
+        tensor.dequantize_into(&mut output);
+        assert_eq!(output, TENSOR_2D_BUFFER_DEQUANTIZED);
+    }
+
     #[test]
     fn tensor_2d_to_tensor_4d() {
         let tensor_2d = Tensor2D::new(
@@ -359,6 +1069,25 @@ mod tests {
         assert_eq!(tensor_4d.buffer, TENSOR_4D_BUFFER_QUANTIZED);
     }
 
+    #[test]
+    fn tensor_2d_reshape_into() {
+        let tensor_2d = Tensor2D::new(
+            TENSOR_4D_TO_TENSOR_2D_BUFFER,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let mut tensor_4d: Tensor4D<i8, 2, 2, 3, 2, 1> = Tensor4D::default();
+        tensor_2d.reshape_into(&mut tensor_4d);
+        assert_eq!(
+            tensor_4d,
+            Tensor4D::new(
+                TENSOR_4D_BUFFER_QUANTIZED,
+                TENSOR_4D_SCALE,
+                TENSOR_4D_ZERO_POINT
+            )
+        );
+    }
+
     #[test]
     fn tensor_4d_new() {
         let tensor = Tensor4D::new(
@@ -373,10 +1102,29 @@ mod tests {
 
     #[test]
     fn tensor_4d_quantize() {
-        let tensor = Tensor4D::quantize(TENSOR_4D_BUFFER, TENSOR_4D_SCALE, TENSOR_4D_ZERO_POINT);
+        let tensor = Tensor4D::quantize(&TENSOR_4D_BUFFER, TENSOR_4D_SCALE, TENSOR_4D_ZERO_POINT);
         assert_eq!(tensor.buffer, TENSOR_4D_BUFFER_QUANTIZED);
     }
 
+    #[test]
+    fn tensor_4d_quantize_from() {
+        let mut tensor = Tensor4D::default();
+        tensor.quantize_from(&TENSOR_4D_BUFFER, TENSOR_4D_SCALE, TENSOR_4D_ZERO_POINT);
+        assert_eq!(
+            tensor,
+            Tensor4D::new(
+                TENSOR_4D_BUFFER_QUANTIZED,
+                TENSOR_4D_SCALE,
+                TENSOR_4D_ZERO_POINT
+            )
+        );
+        tensor.copy_from(&TENSOR_4D_BUFFER_QUANTIZED, [0.5], [1]);
+        assert_eq!(
+            tensor,
+            Tensor4D::new(TENSOR_4D_BUFFER_QUANTIZED, [0.5], [1])
+        );
+    }
+
     #[test]
     fn tensor_4d_dequantize() {
         let tensor = Tensor4D::new(
@@ -387,6 +1135,18 @@ mod tests {
         assert_eq!(tensor.dequantize(), TENSOR_4D_BUFFER);
     }
 
+    #[test]
+    fn tensor_4d_dequantize_into() {
+        let tensor = Tensor4D::new(
+            TENSOR_4D_BUFFER_QUANTIZED,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let mut output = [Buffer2D::from_element([0.; 2]); 2];
+        tensor.dequantize_into(&mut output);
+        assert_eq!(output, TENSOR_4D_BUFFER);
+    }
+
     #[test]
     fn tensor_4d_view() {
         let tensor = Tensor4D::new(
@@ -394,12 +1154,146 @@ mod tests {
             TENSOR_4D_SCALE,
             TENSOR_4D_ZERO_POINT,
         );
-        let view: TensorView<i8, 2, 3, 2> = tensor.view((1, 1), 0, TensorViewPadding::Same, (1, 1));
+        let view: TensorView<i8, 2, 3, 2> =
+            tensor.view((1, 1), 0, TensorViewPadding::Same, (1, 1), (1, 1));
         assert_eq!(view.buffer, TENSOR_4D_VIEW_BUFFER);
         assert_eq!(view.mask, TENSOR_4D_VIEW_MASK);
         assert_eq!(view.len, TENSOR_4D_VIEW_LEN);
     }
 
+    #[test]
+    fn tensor_4d_view_dilated() {
+        let tensor = Tensor4D::new(
+            TENSOR_4D_BUFFER_QUANTIZED,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let view: TensorView<i8, 2, 2, 2> =
+            tensor.view((0, 1), 0, TensorViewPadding::Same, (1, 1), (1, 2));
+        assert_eq!(
+            view.buffer,
+            matrix![
+                [30, 34], [46, 50];
+                [54, 58], [70, 74]
+            ]
+        );
+        assert_eq!(view.mask, matrix![true, true; true, true]);
+        assert_eq!(view.len, 4);
+    }
+
+    #[test]
+    fn tensor_4d_view_strided() {
+        let tensor = Tensor4D::new(
+            TENSOR_4D_BUFFER_QUANTIZED,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        // The even view is not centered: the padding is all on the bottom and on the right
+        let view: TensorView<i8, 3, 2, 2> =
+            tensor.view((0, 1), 1, TensorViewPadding::Same, (2, 2), (1, 1));
+        assert_eq!(
+            view.buffer,
+            matrix![
+                [94,  98],  [0, 0];
+                [118, 122], [0, 0];
+                [0,   0],   [0, 0]
+            ]
+        );
+        assert_eq!(
+            view.mask,
+            matrix![
+                true,  false;
+                true,  false;
+                false, false
+            ]
+        );
+        assert_eq!(view.len, 2);
+    }
+
+    #[test]
+    fn tensor_view_padding_amounts() {
+        assert_eq!(TensorViewPadding::Same.amounts(2, 3, 2), (0, 1));
+        assert_eq!(TensorViewPadding::Same.amounts(3, 2, 2), (0, 1));
+        assert_eq!(TensorViewPadding::Same.amounts(96, 3, 2), (0, 1));
+        assert_eq!(TensorViewPadding::Same.amounts(49, 10, 2), (4, 5));
+        assert_eq!(TensorViewPadding::Same.amounts(5, 4, 1), (1, 2));
+        assert_eq!(TensorViewPadding::Same.amounts(5, 3, 1), (1, 1));
+        assert_eq!(TensorViewPadding::Valid.amounts(5, 4, 1), (0, 0));
+    }
+
+    #[test]
+    fn tensor_4d_broadcast() {
+        let tensor_1 = Tensor4D::new(
+            TENSOR_4D_BUFFER_QUANTIZED,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let tensor_2 = Tensor4D::new(
+            TENSOR_4D_BROADCAST_BUFFER,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let mut output: Tensor4D<i8, 2, 2, 3, 2, 1> = Tensor4D::default();
+        tensor_1.broadcast_into(
+            &tensor_2,
+            |x1, x2| x1 + x2,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+            &mut output,
+        );
+        assert_eq!(output.buffer, TENSOR_4D_BROADCAST_OUTPUT_BUFFER);
+    }
+
+    #[test]
+    fn tensor_4d_broadcast_2d() {
+        let tensor_1 = Tensor4D::new(
+            TENSOR_4D_BUFFER_QUANTIZED,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        // The shape of the 2D tensor is padded to [1, 1, 3, 2]
+        let tensor_2d = Tensor2D::new(
+            matrix![
+                1, 2;
+                3, 4;
+                5, 6
+            ],
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let tensor_4d = Tensor4D::new(
+            [matrix![[1, 2], [3, 4], [5, 6]]],
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let mut expected: Tensor4D<i8, 2, 2, 3, 2, 1> = Tensor4D::default();
+        tensor_1.broadcast_into(
+            &tensor_4d,
+            |x1, x2| x1 - x2,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+            &mut expected,
+        );
+        let mut output: Tensor4D<i8, 2, 2, 3, 2, 1> = Tensor4D::default();
+        tensor_1.broadcast_into(
+            &tensor_2d,
+            |x1, x2| x1 - x2,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+            &mut output,
+        );
+        assert_eq!(output, expected);
+        let mut output: Tensor4D<i8, 2, 2, 3, 2, 1> = Tensor4D::default();
+        tensor_2d.broadcast_into(
+            &tensor_1,
+            |x2, x1| x1 - x2,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+            &mut output,
+        );
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn tensor_4d_to_tensor_2d() {
         let tensor_4d = Tensor4D::new(
@@ -410,4 +1304,23 @@ mod tests {
         let tensor_2d: Tensor2D<i8, 2, 12, 1> = Tensor2D::from(tensor_4d);
         assert_eq!(tensor_2d.buffer, TENSOR_4D_TO_TENSOR_2D_BUFFER);
     }
+
+    #[test]
+    fn tensor_4d_reshape_into() {
+        let tensor_4d = Tensor4D::new(
+            TENSOR_4D_BUFFER_QUANTIZED,
+            TENSOR_4D_SCALE,
+            TENSOR_4D_ZERO_POINT,
+        );
+        let mut tensor_2d: Tensor2D<i8, 2, 12, 1> = Tensor2D::default();
+        tensor_4d.reshape_into(&mut tensor_2d);
+        assert_eq!(
+            tensor_2d,
+            Tensor2D::new(
+                TENSOR_4D_TO_TENSOR_2D_BUFFER,
+                TENSOR_4D_SCALE,
+                TENSOR_4D_ZERO_POINT
+            )
+        );
+    }
 }
@@ -0,0 +1,47 @@
+use microflow::buffer::Buffer2D;
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[path = "../samples/features/speech.rs"]
+mod speech_features;
+
+#[model("models/speech.tflite")]
+struct Speech;
+
+/// Returns the batch of the two speech samples.
+fn samples() -> Buffer2D<i8, 2, 1960> {
+    Buffer2D::from_fn(|i, j| match i {
+        0 => speech_features::YES[j],
+        _ => speech_features::NO[j],
+    })
+}
+
+// Every tensor computed at inference time has a batch of two
+model_test!(
+    speech_batch,
+    "models/speech_batch.tflite",
+    predict_quantized,
+    [(
+        samples(),
+        matrix![
+            0., 0.,        0.99609375, 0.;
+            0., 0.0546875, 0.,         0.9453125
+        ]
+    )]
+);
+
+#[test]
+fn speech_batch_model_per_sample() {
+    let output = speech_batch::Model::predict_quantized(samples());
+    assert_eq!(
+        output.row(0),
+        Speech::predict_quantized(speech_features::YES).row(0)
+    );
+    assert_eq!(
+        output.row(1),
+        Speech::predict_quantized(speech_features::NO).row(0)
+    );
+}
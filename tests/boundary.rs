@@ -0,0 +1,30 @@
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[model("models/sine.tflite")]
+struct Sine;
+
+#[model("models/sine_float.tflite")]
+struct SineFloat;
+
+#[model("models/sine_uint8.tflite")]
+struct SineUint8;
+
+#[test]
+fn sine_float_model() {
+    for i in 0..26 {
+        let input = matrix![i as f32 * 0.25];
+        assert_eq!(SineFloat::predict(input), Sine::predict(input));
+    }
+}
+
+#[test]
+fn sine_uint8_model() {
+    for i in 0..26 {
+        let input = matrix![i as f32 * 0.25];
+        assert_eq!(SineUint8::predict(input), Sine::predict(input));
+    }
+    let input = matrix![20u8];
+    let output = matrix![0.41348344];
+    assert_eq!(SineUint8::predict_quantized(input), output);
+}
@@ -0,0 +1,44 @@
+use microflow::buffer::Buffer2D;
+use microflow_macros::model;
+use nalgebra::SMatrix;
+
+#[model("models/speech_add_rank_1.tflite")]
+struct SpeechAddRank1;
+
+#[model("models/speech_add_rank_2.tflite")]
+struct SpeechAddRank2;
+
+#[model("models/speech_add_rank_4.tflite")]
+struct SpeechAddRank4;
+
+#[model("models/speech_add_rank_1.tflite", integer_only)]
+struct SpeechAddRank1IntegerOnly;
+
+#[model("models/speech_add_rank_4.tflite", integer_only)]
+struct SpeechAddRank4IntegerOnly;
+
+const OFFSET: [f32; 8] = [-0.5, -0.25, 0., 0.25, 0.5, 1., 2., 4.];
+
+#[test]
+fn speech_add_lower_rank_model() {
+    // The offsets of rank 1 and 2 are padded with leading 1s, as the one of rank 4 already is
+    let input = Buffer2D::from_element(0.5);
+    let output = SpeechAddRank4::predict((input, [SMatrix::from([[OFFSET]])]));
+    assert_eq!(
+        SpeechAddRank1::predict((input, Buffer2D::from_fn(|_, c| OFFSET[c]))),
+        output
+    );
+    assert_eq!(
+        SpeechAddRank2::predict((input, Buffer2D::from_fn(|_, c| OFFSET[c]))),
+        output
+    );
+}
+
+#[test]
+fn speech_add_lower_rank_model_integer_only() {
+    let input = Buffer2D::from_element(0.5);
+    assert_eq!(
+        SpeechAddRank1IntegerOnly::predict((input, Buffer2D::from_fn(|_, c| OFFSET[c]))),
+        SpeechAddRank4IntegerOnly::predict((input, [SMatrix::from([[OFFSET]])]))
+    );
+}
@@ -0,0 +1,25 @@
+/// Defines a module testing the given model, with both the floating-point and the integer-only
+/// requantization, by predicting each input and comparing it with the expected output.
+macro_rules! model_test {
+    ($name:ident, $path:tt, $predict:ident, [$(($input:expr, $output:expr)),+ $(,)?]) => {
+        mod $name {
+            use super::*;
+
+            #[microflow_macros::model($path)]
+            pub struct Model;
+
+            #[microflow_macros::model($path, integer_only)]
+            pub struct ModelIntegerOnly;
+
+            #[test]
+            fn model() {
+                $(assert_eq!(Model::$predict($input), $output);)+
+            }
+
+            #[test]
+            fn model_integer_only() {
+                $(assert_eq!(ModelIntegerOnly::$predict($input), $output);)+
+            }
+        }
+    };
+}
@@ -0,0 +1,18 @@
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[path = "../samples/features/speech.rs"]
+mod speech_features;
+
+// The input is reshaped to two channels, expanded to eight by the DepthwiseConv2D operator
+model_test!(
+    speech_depth_multiplier,
+    "models/speech_depth_multiplier.tflite",
+    predict_quantized,
+    [
+        (speech_features::YES, matrix![0., 0., 0.99609375, 0.]),
+        (speech_features::NO, matrix![0., 0.03125, 0., 0.96875])
+    ]
+);
@@ -0,0 +1,24 @@
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[path = "../samples/features/speech.rs"]
+mod speech_features;
+
+// The DepthwiseConv2D operator is dilated by 2 on the height and by 3 on the width
+model_test!(
+    speech_dilated,
+    "models/speech_dilated.tflite",
+    predict_quantized,
+    [
+        (
+            speech_features::YES,
+            matrix![0., 0.00390625, 0.99609375, 0.]
+        ),
+        (
+            speech_features::NO,
+            matrix![0., 0.49609375, 0.01171875, 0.49609375]
+        )
+    ]
+);
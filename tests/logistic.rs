@@ -0,0 +1,12 @@
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[model("models/sine_logistic.tflite")]
+struct SineLogistic;
+
+#[test]
+fn sine_logistic_model() {
+    assert_eq!(SineLogistic::predict(matrix![0.5]), matrix![0.6015625]);
+    assert_eq!(SineLogistic::predict(matrix![2.]), matrix![0.7109375]);
+    assert_eq!(SineLogistic::predict(matrix![-1.5]), matrix![0.5078125]);
+}
@@ -0,0 +1,122 @@
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[model("models/sine_exp.tflite")]
+struct SineExp;
+
+#[model("models/sine_log.tflite")]
+struct SineLog;
+
+#[model("models/sine_sqrt.tflite")]
+struct SineSqrt;
+
+#[model("models/sine_rsqrt.tflite")]
+struct SineRsqrt;
+
+#[model("models/sine_abs.tflite")]
+struct SineAbs;
+
+#[model("models/sine_neg.tflite")]
+struct SineNeg;
+
+#[model("models/sine_floor.tflite")]
+struct SineFloor;
+
+#[model("models/sine_elu.tflite")]
+struct SineElu;
+
+#[model("models/sine_gelu.tflite")]
+struct SineGelu;
+
+#[model("models/sine_hard_swish.tflite")]
+struct SineHardSwish;
+
+#[test]
+fn sine_exp_model() {
+    assert_eq!(SineExp::predict(matrix![0.5]), matrix![1.515625]);
+    assert_eq!(SineExp::predict(matrix![2.]), matrix![2.46875]);
+    assert_eq!(SineExp::predict(matrix![-1.5]), matrix![1.03125]);
+}
+
+#[test]
+fn sine_log_model() {
+    assert_eq!(SineLog::predict(matrix![0.5]), matrix![-0.875]);
+    assert_eq!(SineLog::predict(matrix![2.]), matrix![-0.09375]);
+    // The logarithm of negative values is undefined, saturated to the minimum
+    assert_eq!(SineLog::predict(matrix![4.5]), matrix![-7.96875]);
+}
+
+#[test]
+fn sine_sqrt_model() {
+    assert_eq!(SineSqrt::predict(matrix![0.5]), matrix![0.640625]);
+    assert_eq!(SineSqrt::predict(matrix![2.]), matrix![0.953125]);
+    // The square root of negative values is undefined, saturated to the minimum
+    assert_eq!(SineSqrt::predict(matrix![4.5]), matrix![0.]);
+}
+
+#[test]
+fn sine_rsqrt_model() {
+    assert_eq!(SineRsqrt::predict(matrix![0.5]), matrix![1.5625]);
+    assert_eq!(SineRsqrt::predict(matrix![2.]), matrix![1.0625]);
+    assert_eq!(SineRsqrt::predict(matrix![3.1]), matrix![5.5]);
+    // The reciprocal square root of negative values is undefined, saturated to the minimum
+    assert_eq!(SineRsqrt::predict(matrix![4.5]), matrix![0.]);
+}
+
+#[test]
+fn sine_abs_model() {
+    assert_eq!(SineAbs::predict(matrix![0.5]), matrix![0.4140625]);
+    assert_eq!(SineAbs::predict(matrix![4.5]), matrix![1.1171875]);
+    assert_eq!(SineAbs::predict(matrix![5.5]), matrix![0.6640625]);
+}
+
+#[test]
+fn sine_neg_model() {
+    assert_eq!(SineNeg::predict(matrix![0.5]), matrix![-0.4140625]);
+    assert_eq!(SineNeg::predict(matrix![5.5]), matrix![0.6640625]);
+    // The result saturates to the maximum of the output tensor
+    assert_eq!(SineNeg::predict(matrix![4.5]), matrix![0.9921875]);
+}
+
+#[test]
+fn sine_floor_model() {
+    assert_eq!(SineFloor::predict(matrix![0.5]), matrix![0.]);
+    assert_eq!(SineFloor::predict(matrix![4.5]), matrix![-2.]);
+    assert_eq!(SineFloor::predict(matrix![5.5]), matrix![-1.]);
+}
+
+#[test]
+fn sine_elu_model() {
+    assert_eq!(SineElu::predict(matrix![0.5]), matrix![0.4140625]);
+    assert_eq!(SineElu::predict(matrix![2.]), matrix![0.8984375]);
+    assert_eq!(SineElu::predict(matrix![4.5]), matrix![-0.671875]);
+}
+
+#[test]
+fn sine_gelu_model() {
+    assert_eq!(SineGelu::predict(matrix![0.5]), matrix![0.2734375]);
+    assert_eq!(SineGelu::predict(matrix![2.]), matrix![0.734375]);
+    assert_eq!(SineGelu::predict(matrix![-1.5]), matrix![0.015625]);
+}
+
+#[test]
+fn sine_hard_swish_model() {
+    assert_eq!(SineHardSwish::predict(matrix![0.5]), matrix![0.234375]);
+    assert_eq!(SineHardSwish::predict(matrix![2.]), matrix![0.5859375]);
+    assert_eq!(SineHardSwish::predict(matrix![-1.5]), matrix![0.015625]);
+}
+
+// The output is requantized to int16 and the Logistic operator looks up a table of 65536 entries
+model_test!(
+    sine_int16_logistic,
+    "models/sine_int16_logistic.tflite",
+    predict,
+    [
+        (matrix![0.5], matrix![0.6019287]),
+        (matrix![2.], matrix![0.7112427]),
+        (matrix![4.5], matrix![0.25759888])
+    ]
+);
@@ -0,0 +1,39 @@
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[model("models/sine.tflite")]
+struct Sine;
+
+#[model("models/sine_multi_io.tflite")]
+struct SineMultiIo;
+
+#[test]
+fn sine_multi_io_model() {
+    let input = matrix![0.5];
+    let output = SineMultiIo::predict((input, matrix![0.25]));
+    assert_eq!(output.sine, Sine::predict(input));
+    assert_eq!(output.sum, matrix![0.7]);
+}
+
+#[test]
+fn sine_multi_io_model_quantized() {
+    let output = SineMultiIo::predict_quantized((matrix![-108], matrix![8]));
+    assert_eq!(
+        output,
+        SineMultiIoOutput {
+            sine: matrix![0.41348344],
+            sum: matrix![0.7],
+        }
+    );
+}
+
+#[test]
+fn sine_multi_io_model_quantized_into() {
+    let mut output = SineMultiIoOutput {
+        sine: matrix![0.],
+        sum: matrix![0.],
+    };
+    SineMultiIo::predict_quantized_into(&(matrix![-108], matrix![8]), &mut output);
+    assert_eq!(output.sine, matrix![0.41348344]);
+    assert_eq!(output.sum, matrix![0.7]);
+}
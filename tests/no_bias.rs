@@ -0,0 +1,15 @@
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+// The first FullyConnected operator omits the biases, while the others have index -1
+model_test!(
+    sine_no_bias,
+    "models/sine_no_bias.tflite",
+    predict,
+    [
+        (matrix![0.], matrix![0.]),
+        (matrix![2.], matrix![0.09096635])
+    ]
+);
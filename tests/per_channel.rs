@@ -0,0 +1,28 @@
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[model("models/sine.tflite")]
+struct Sine;
+
+model_test!(
+    sine_per_channel,
+    "models/sine_per_channel.tflite",
+    predict,
+    [
+        (matrix![0.5], matrix![0.41348344]),
+        (matrix![2.], matrix![0.90966356])
+    ]
+);
+
+#[test]
+fn sine_per_channel_model_close_to_per_tensor() {
+    // The weights are requantized on each channel, so the outputs differ by at most one step
+    for x in [-1.5, 0., 0.5, 1., 2., 3.] {
+        let input = matrix![x];
+        let difference = (sine_per_channel::Model::predict(input) - Sine::predict(input)).abs();
+        assert!(difference[0] <= 0.0083);
+    }
+}
@@ -5,9 +5,40 @@ use nalgebra::matrix;
 #[model("models/person_detect.tflite")]
 struct PersonDetect;
 
+#[model("models/person_detect.tflite", integer_only)]
+struct PersonDetectIntegerOnly;
+
+#[model("models/person_detect.tflite", raw_weights)]
+struct PersonDetectRawWeights;
+
 #[test]
+#[cfg_attr(feature = "integer-only", ignore)]
 fn person_detect_model() {
     let input = [Buffer2D::from_element([0.5])];
-    let output = matrix![0.8046875, 0.1953125];
+    let output = matrix![0.7109375, 0.2890625];
     assert_eq!(PersonDetect::predict(input), output);
 }
+
+#[test]
+#[cfg_attr(feature = "integer-only", ignore)]
+fn person_detect_model_into() {
+    let input = [Buffer2D::from_element([0.5])];
+    let mut output = Buffer2D::zeros();
+    PersonDetect::predict_into(&input, &mut output);
+    assert_eq!(output, matrix![0.7109375, 0.2890625]);
+}
+
+#[test]
+fn person_detect_model_integer_only() {
+    let input = [Buffer2D::from_element([0.5])];
+    let output = matrix![0.71875, 0.28125];
+    assert_eq!(PersonDetectIntegerOnly::predict(input), output);
+}
+
+#[test]
+#[cfg_attr(feature = "integer-only", ignore)]
+fn person_detect_model_raw_weights() {
+    let input = [Buffer2D::from_element([0.5])];
+    let output = matrix![0.7109375, 0.2890625];
+    assert_eq!(PersonDetectRawWeights::predict(input), output);
+}
@@ -0,0 +1,20 @@
+#![cfg(feature = "saturation-counter")]
+
+use microflow::quantize::{reset_saturation_count, saturation_count};
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[model("models/sine.tflite")]
+struct Sine;
+
+#[test]
+fn sine_saturation_counter() {
+    reset_saturation_count();
+    Sine::predict(matrix![0.5]);
+    assert_eq!(saturation_count(), 0);
+    // The input is far outside of the quantization range of the model
+    Sine::predict(matrix![100.]);
+    assert!(saturation_count() > 0);
+    reset_saturation_count();
+    assert_eq!(saturation_count(), 0);
+}
@@ -4,9 +4,19 @@ use nalgebra::matrix;
 #[model("models/sine.tflite")]
 struct Sine;
 
+#[model("models/sine.tflite", integer_only)]
+struct SineIntegerOnly;
+
 #[test]
 fn sine_model() {
     let input = matrix![0.5];
     let output = matrix![0.41348344];
     assert_eq!(Sine::predict(input), output);
 }
+
+#[test]
+fn sine_model_integer_only() {
+    let input = matrix![0.5];
+    let output = matrix![0.41348344];
+    assert_eq!(SineIntegerOnly::predict(input), output);
+}
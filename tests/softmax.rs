@@ -0,0 +1,24 @@
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[path = "../samples/features/speech.rs"]
+mod speech_features;
+
+// A Softmax operator with a beta of 0.5 is applied along the channels of the DepthwiseConv2D output
+model_test!(
+    speech_softmax_4d,
+    "models/speech_softmax_4d.tflite",
+    predict_quantized,
+    [
+        (
+            speech_features::YES,
+            matrix![0.0859375, 0.28125, 0.40234375, 0.234375]
+        ),
+        (
+            speech_features::NO,
+            matrix![0.078125, 0.3984375, 0.16015625, 0.36328125]
+        )
+    ]
+);
@@ -5,9 +5,19 @@ use nalgebra::matrix;
 #[model("models/speech.tflite")]
 struct Speech;
 
+#[model("models/speech.tflite", integer_only)]
+struct SpeechIntegerOnly;
+
 #[test]
 fn speech_model() {
     let input = Buffer2D::from_element(0.5);
     let output = matrix![0.15625, 0.2734375, 0.2734375, 0.296875];
     assert_eq!(Speech::predict(input), output);
 }
+
+#[test]
+fn speech_model_integer_only() {
+    let input = Buffer2D::from_element(0.5);
+    let output = matrix![0.15625, 0.2734375, 0.2734375, 0.296875];
+    assert_eq!(SpeechIntegerOnly::predict(input), output);
+}
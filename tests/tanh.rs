@@ -0,0 +1,26 @@
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[macro_use]
+mod common;
+
+#[model("models/sine_tanh.tflite")]
+struct SineTanh;
+
+#[test]
+fn sine_tanh_model() {
+    assert_eq!(SineTanh::predict(matrix![0.5]), matrix![0.390625]);
+    assert_eq!(SineTanh::predict(matrix![2.]), matrix![0.71875]);
+    assert_eq!(SineTanh::predict(matrix![-1.5]), matrix![0.03125]);
+}
+
+model_test!(
+    sine_fused_tanh,
+    "models/sine_fused_tanh.tflite",
+    predict,
+    [
+        (matrix![0.5], matrix![0.3886744]),
+        (matrix![2.], matrix![0.71946114]),
+        (matrix![-1.5], matrix![0.033078674])
+    ]
+);
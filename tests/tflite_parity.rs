@@ -0,0 +1,113 @@
+//! Bit-exact parity of the `tflite-parity` mode with TensorFlow Lite Micro.
+//! The expected outputs were computed with a port of the `int8` reference kernels of TensorFlow
+//! Lite Micro, on the bundled samples and on pseudo-random inputs, by
+//! `analysis/parity/generate.py`.
+#![cfg(feature = "tflite-parity")]
+
+use microflow::buffer::{Buffer2D, Buffer4D};
+use microflow_macros::model;
+use nalgebra::matrix;
+
+#[path = "../samples/features/person_detect.rs"]
+mod person_detect_features;
+#[path = "../samples/features/speech.rs"]
+mod speech_features;
+
+#[model("models/sine.tflite")]
+struct Sine;
+
+#[model("models/speech.tflite")]
+struct Speech;
+
+#[model("models/person_detect.tflite")]
+struct PersonDetect;
+
+/// Generates the input element with the given (row-major) index, deterministically.
+fn noise(seed: usize, index: usize) -> i8 {
+    ((index + seed * 7919) as u32)
+        .wrapping_mul(2654435761)
+        .to_be_bytes()[0] as i8
+}
+
+#[test]
+fn sine_parity() {
+    let outputs = [
+        0.033078674,
+        0.23155072,
+        0.41348344,
+        0.6781128,
+        0.8765849,
+        0.98409057,
+        0.98409057,
+        0.9758209,
+        0.9013939,
+        0.74427015,
+        0.5457981,
+        0.3390564,
+        0.12404503,
+        -0.08269668,
+        -0.29770806,
+        -0.5209891,
+        -0.74427015,
+        -0.9427422,
+        -1.1164052,
+        -1.0006299,
+        -0.8931242,
+        -0.7856185,
+        -0.66157347,
+        -0.5540678,
+        -0.43829244,
+        -0.3390564,
+    ];
+    for (i, output) in outputs.into_iter().enumerate() {
+        let input = matrix![i as f32 * 0.25];
+        assert_eq!(Sine::predict(input), matrix![output]);
+    }
+}
+
+#[test]
+fn speech_parity() {
+    assert_eq!(
+        Speech::predict_quantized(speech_features::YES),
+        matrix![0., 0., 0.99609375, 0.]
+    );
+    assert_eq!(
+        Speech::predict_quantized(speech_features::NO),
+        matrix![0., 0.0546875, 0., 0.9453125]
+    );
+    let outputs = [
+        matrix![0., 0.05078125, 0.078125, 0.87109375],
+        matrix![0., 0.015625, 0.015625, 0.96875],
+        matrix![0., 0.0234375, 0.10546875, 0.87109375],
+        matrix![0., 0.06640625, 0.203125, 0.73046875],
+    ];
+    for (i, output) in outputs.into_iter().enumerate() {
+        let input: Buffer2D<i8, 1, 1960> = Buffer2D::from_fn(|_, j| noise(i + 1, j));
+        assert_eq!(Speech::predict_quantized(input), output);
+    }
+}
+
+#[test]
+fn person_detect_parity() {
+    assert_eq!(
+        PersonDetect::predict_quantized(person_detect_features::PERSON),
+        matrix![0.05859375, 0.94140625]
+    );
+    assert_eq!(
+        PersonDetect::predict_quantized(person_detect_features::NO_PERSON),
+        matrix![0.72265625, 0.27734375]
+    );
+    assert_eq!(
+        PersonDetect::predict([Buffer2D::from_element([0.5])]),
+        matrix![0.71875, 0.28125]
+    );
+    let outputs = [
+        matrix![0.7890625, 0.2109375],
+        matrix![0.82421875, 0.17578125],
+    ];
+    for (i, output) in outputs.into_iter().enumerate() {
+        let input: Buffer4D<i8, 1, 96, 96, 1> =
+            [Buffer2D::from_fn(|r, c| [noise(i + 1, 96 * r + c)])];
+        assert_eq!(PersonDetect::predict_quantized(input), output);
+    }
+}